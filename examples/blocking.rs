@@ -0,0 +1,46 @@
+use dpmaster::{
+    blocking::{BlockingGame, BlockingMaster},
+    GameName, GetServers, GetServersFilter, ProtocolVersion,
+};
+use std::{net::ToSocketAddrs, time::Duration};
+
+const MASTER_SERVER_ADDR: &str = "master1.forbidden.gg:27950";
+const APPLICATION: &[u8] = b"Warfork";
+const APP_PROTOCOL_VERSION: &[u8] = b"26";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let master_socket_addr = MASTER_SERVER_ADDR
+        .to_socket_addrs()?
+        .next()
+        .ok_or("Master server address did not resolve")?;
+
+    let get_servers = GetServers {
+        game_name: Some(GameName::new(APPLICATION.into())?),
+        protocol_version: ProtocolVersion::new(APP_PROTOCOL_VERSION.into())?,
+        filter: GetServersFilter {
+            empty: true,
+            full: true,
+            ..Default::default()
+        },
+    };
+
+    let mut master = BlockingMaster::new(master_socket_addr)?;
+    let servers = master.get_servers(&get_servers, Duration::from_secs(1))?;
+    println!("{} servers", servers.addresses.len());
+
+    for server_addr in servers.addresses {
+        let mut game = BlockingGame::new(server_addr.into())?;
+        match game.get_info(b"1234", Duration::from_secs(2)) {
+            Ok(info) => {
+                let name = info
+                    .hostname()
+                    .map(|name| String::from_utf8_lossy(name).into_owned())
+                    .unwrap_or_default();
+                println!("{server_addr}: {name}");
+            }
+            Err(e) => println!("{server_addr}: {e}"),
+        }
+    }
+
+    Ok(())
+}
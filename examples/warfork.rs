@@ -1,9 +1,9 @@
 use dpmaster::{
-    Challenge, GameName, GetServers, GetServersFilter, GetServersResponse, GetStatus,
-    ProtocolVersion, StatusResponse,
+    color::strip_color_codes, Challenge, GameName, GetServers, GetServersFilter,
+    GetServersResponse, GetStatus, ProtocolVersion, StatusResponse,
 };
 use futures::StreamExt;
-use std::{io::Cursor, net::ToSocketAddrs, time::Duration};
+use std::{net::ToSocketAddrs, time::Duration};
 use tokio::{net::UdpSocket, select};
 
 const MASTER_SERVER_ADDR: &str = "master1.forbidden.gg:27950";
@@ -28,15 +28,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let mut recv_buf = [0; 1024];
-    let mut send_buf = Cursor::new([0; 1024]);
-    get_servers.write_all_to(&mut send_buf)?;
+    let mut send_buf = [0; 1024];
+    let written = get_servers.write_all_to(&mut send_buf[..])?;
     let _ = udp
-        .send_to(
-            &send_buf.get_ref()[..send_buf.position() as usize],
-            master_socket_addr,
-        )
+        .send_to(&send_buf[..written], master_socket_addr)
         .await?;
-    send_buf.set_position(0);
 
     let mut server_addresses = vec![];
     loop {
@@ -66,21 +62,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     return None;
                 }
             };
-            let mut send_buf = Cursor::new([0; 1024]);
+            let mut send_buf = [0; 1024];
             let get_status = GetStatus::new(Challenge::new(b"1234".into()).unwrap());
-            match get_status.write_all_to(&mut send_buf) {
-                Ok(()) => {}
+            let written = match get_status.write_all_to(&mut send_buf[..]) {
+                Ok(written) => written,
                 Err(_) => {
                     panic!()
                 }
-            }
-            match udp
-                .send_to(
-                    &send_buf.get_ref()[..send_buf.position() as usize],
-                    server_addr,
-                )
-                .await
-            {
+            };
+            match udp.send_to(&send_buf[..written], server_addr).await {
                 Ok(_) => {}
                 Err(e) => {
                     println!("Udp send error: {e}");
@@ -118,14 +108,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .buffer_unordered(16)
         .collect::<Vec<_>>()
         .await;
-    let responses = responses.into_iter().filter_map(|s| s).collect::<Vec<_>>();
+    let responses = responses.into_iter().flatten().collect::<Vec<_>>();
 
     for (addr, response) in &responses {
         // if response.player_infos.is_empty() {
         //     continue;
         // }
-        let mut infos = response.key_values.iter().collect::<Vec<_>>();
-        infos.sort_by(|a, b| a.0.cmp(b.0));
+        let mut infos = response.iter_str().collect::<Vec<_>>();
+        infos.sort_by(|a, b| a.0.cmp(&b.0));
         let name = &response.key_values[b"sv_hostname".as_slice()];
         let gametype = &response.key_values[b"gametype".as_slice()];
         println!(
@@ -135,20 +125,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             addr
         );
         for (k, v) in infos {
-            if k == b"sv_hostname" || k == b"gametype" {
+            if k == "sv_hostname" || k == "gametype" {
                 continue;
             }
-            let k = std::str::from_utf8(k).unwrap();
-            let v = clean_rich_text(std::str::from_utf8(v).unwrap());
+            let v = clean_rich_text(&v);
             println!("    {k:>22} {v}")
         }
         println!();
         println!("    PING   FRAGS   TEAM NAME");
         for player in &response.player_infos {
-            let name = clean_rich_text(&player.name);
+            let name = clean_rich_text(&player.name_lossy());
+            let team = player.team.map(|t| t.to_string()).unwrap_or_default();
             println!(
                 "    {:<6} {:<7} {:<4} {}",
-                player.ping, player.frags, player.team, name
+                player.ping, player.frags, team, name
             );
         }
         println!();
@@ -158,10 +148,5 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn clean_rich_text(s: &str) -> String {
-    let to_remove = ["^0", "^1", "^2", "^3", "^4", "^5", "^6", "^7", "^8", "^9"];
-    let mut s = s.to_string();
-    for c in to_remove {
-        s = s.replace(c, "");
-    }
-    s
+    String::from_utf8_lossy(&strip_color_codes(s.as_bytes())).into_owned()
 }
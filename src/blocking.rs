@@ -0,0 +1,1570 @@
+//! Blocking clients built on top of [`std::net::UdpSocket`], for consumers
+//! that don't want to pull in an async runtime.
+
+use crate::{
+    Challenge, ChallengeResponse, DatagramInfo, GetChallenge, GetInfo, GetMotd, GetServers,
+    GetServersExt, GetServersExtResponse, GetServersResponse, GetStatus, InfoResponse, Motd,
+    NewChallengeError, ParseResponseError, Print, StatusResponse,
+};
+use nom_supreme::error::ErrorTree;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Default size of the send and receive buffers, see `with_capacity` to
+/// use another size.
+pub const MAX_PACKET_LEN: usize = 1400;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Timed out waiting for a response")]
+    Timeout,
+    #[error("Received an invalid response: {0}")]
+    InvalidResponse(#[from] ParseResponseError),
+    #[error(transparent)]
+    InvalidChallenge(#[from] NewChallengeError),
+    #[error("Received a datagram that doesn't fit in the receive buffer")]
+    Truncated,
+    #[error("The response doesn't echo the challenge of the request")]
+    ChallengeMismatch,
+}
+
+/// Outcome of [`BlockingGame::probe_connect`].
+#[derive(Debug)]
+pub enum ProbeResult {
+    /// The server answered with a challenge, it would accept a "connect".
+    Accepted(ChallengeResponse),
+    /// The server answered with a "print" message instead, usually
+    /// explaining why the client can't connect (banned, full...).
+    Rejected(String),
+    /// No answer before the timeout.
+    Timeout,
+}
+
+/// Reject an "infoResponse" whose `challenge` key isn't the one sent, as
+/// it may be stale or spoofed.
+fn check_challenge(response: InfoResponse, challenge: &[u8]) -> Result<InfoResponse, ClientError> {
+    if response.get(b"challenge") != Some(challenge) {
+        return Err(ClientError::ChallengeMismatch);
+    }
+    Ok(response)
+}
+
+fn invalid_response(bytes: &[u8], error: ErrorTree<&[u8]>) -> ClientError {
+    ParseResponseError::from_error_tree(bytes, error).into()
+}
+
+/// Any local address of the same family as `remote`.
+fn unspecified_local_addr(remote: SocketAddr) -> SocketAddr {
+    match remote {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    }
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Receive a single datagram from `from` into `buf`, ignoring datagrams
+/// from any other address. Returns `Ok(None)` once `timeout` elapsed.
+///
+/// A datagram filling the whole buffer is assumed to be truncated, so `buf`
+/// should be one byte longer than the largest datagram expected. Socket
+/// errors other than a timeout, such as a connection refused reported by
+/// ICMP, are returned as [`ClientError::Io`] rather than retried.
+fn recv_from_addr(
+    socket: &UdpSocket,
+    from: SocketAddr,
+    buf: &mut [u8],
+    timeout: Duration,
+) -> Result<Option<usize>, ClientError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match socket.recv_from(buf) {
+            Ok((written, addr)) if addr == from => {
+                if written == buf.len() {
+                    return Err(ClientError::Truncated);
+                }
+                return Ok(Some(written));
+            }
+            Ok(_) => continue,
+            Err(e) if is_timeout(&e) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Parse the addresses of a single "getserversResponse" or
+/// "getserversExtResponse" datagram, whether it contains an EOT and how
+/// many entries were skipped.
+type ParseDatagram<A> = fn(&[u8]) -> Result<(Vec<A>, bool, usize), ClientError>;
+
+fn parse_get_servers(bytes: &[u8]) -> Result<(Vec<SocketAddrV4>, bool, usize), ClientError> {
+    let response = GetServersResponse::parse(bytes).map_err(|e| invalid_response(bytes, e))?;
+    Ok((response.addresses, response.eot, response.skipped))
+}
+
+fn parse_get_servers_ext(bytes: &[u8]) -> Result<(Vec<SocketAddr>, bool, usize), ClientError> {
+    let response = GetServersExtResponse::parse(bytes).map_err(|e| invalid_response(bytes, e))?;
+    let eot = matches!(response.datagram_info, DatagramInfo::Eot);
+    Ok((response.addresses, eot, 0))
+}
+
+fn collected_datagram_info(eot: bool) -> DatagramInfo {
+    if eot {
+        DatagramInfo::Eot
+    } else {
+        DatagramInfo::Collected
+    }
+}
+
+/// Timeouts, retries and limits of a query, taken by the `_with` methods
+/// of [`BlockingMaster`] and [`BlockingGame`].
+#[derive(Debug, Clone)]
+pub struct QueryConfig {
+    /// How long to wait for a response after each send.
+    pub timeout: Duration,
+    /// How long to wait for each following datagram of a master response
+    /// once the first one arrived.
+    pub idle_timeout: Duration,
+    /// Give up this long after the request was first sent, even if
+    /// datagrams keep arriving or resends are left.
+    pub deadline: Option<Duration>,
+    /// Stop collecting a master response once this many distinct
+    /// addresses were received, even before an EOT.
+    pub max_servers: Option<usize>,
+    /// Number of times the request is resent when no response arrives.
+    pub retries: u32,
+    /// Delay before the first resend, doubled for every following one.
+    pub backoff: Duration,
+    /// Upper bound of a random delay added to each backoff, so that many
+    /// clients don't resend in lockstep.
+    pub jitter: Duration,
+}
+
+impl QueryConfig {
+    /// Wait up to `timeout` for a response, and for each following
+    /// datagram of a master response, without resending.
+    pub fn with_timeout(timeout: Duration) -> QueryConfig {
+        QueryConfig {
+            timeout,
+            idle_timeout: timeout,
+            deadline: None,
+            max_servers: None,
+            retries: 0,
+            backoff: Duration::ZERO,
+            jitter: Duration::ZERO,
+        }
+    }
+
+    fn delay_before(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .saturating_add(random_up_to(self.jitter))
+    }
+
+    fn collect_limits(&self, deadline: Option<Instant>) -> CollectLimits {
+        CollectLimits {
+            first_timeout: self.timeout,
+            idle_timeout: self.idle_timeout,
+            deadline,
+            max_servers: self.max_servers,
+        }
+    }
+}
+
+impl Default for QueryConfig {
+    fn default() -> QueryConfig {
+        QueryConfig {
+            retries: 2,
+            backoff: Duration::from_millis(500),
+            jitter: Duration::from_millis(250),
+            ..QueryConfig::with_timeout(Duration::from_secs(1))
+        }
+    }
+}
+
+/// A random duration up to `max`.
+#[cfg(feature = "rand")]
+fn random_up_to(max: Duration) -> Duration {
+    max.mul_f64(rand::random::<f64>())
+}
+
+/// A random duration up to `max`.
+///
+/// Without the `rand` feature, the randomness comes from std: every
+/// `RandomState` gets random SipHash keys, so the hash of an empty input
+/// is a random `u64`. This is plenty to spread out resends.
+#[cfg(not(feature = "rand"))]
+fn random_up_to(max: Duration) -> Duration {
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+    };
+
+    let random = RandomState::new().build_hasher().finish();
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+/// What was received while collecting the datagrams of a response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectionStats {
+    pub datagrams: usize,
+    /// Size of all the datagrams.
+    pub bytes: usize,
+    pub had_eot: bool,
+    /// Datagrams from the master that couldn't be parsed, which are
+    /// skipped rather than failing the whole response.
+    pub invalid: usize,
+}
+
+/// When to stop collecting the datagrams of a response, besides an EOT.
+#[derive(Clone, Copy)]
+struct CollectLimits {
+    /// Longest wait for the first datagram.
+    first_timeout: Duration,
+    /// Longest wait for each following datagram.
+    idle_timeout: Duration,
+    /// Stop waiting altogether at this instant.
+    deadline: Option<Instant>,
+    /// Stop once this many distinct addresses were received.
+    max_servers: Option<usize>,
+}
+
+impl CollectLimits {
+    fn next_timeout(&self, received: usize) -> Duration {
+        let timeout = if received == 0 {
+            self.first_timeout
+        } else {
+            self.idle_timeout
+        };
+        match self.deadline {
+            Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => timeout,
+        }
+    }
+}
+
+/// A blocking client to a master server.
+pub struct BlockingMaster {
+    socket: UdpSocket,
+    addr: SocketAddr,
+    send_buf: Vec<u8>,
+    recv_buf: Vec<u8>,
+    connected: bool,
+    /// Unconnected socket to reach game servers while `socket` is
+    /// connected to the master, bound on first use.
+    game_socket: Option<UdpSocket>,
+}
+
+impl BlockingMaster {
+    /// Bind a new socket on `0.0.0.0:0`, or `[::]:0` if `addr` is IPv6,
+    /// connected to the master at `addr`.
+    pub fn new(addr: SocketAddr) -> io::Result<BlockingMaster> {
+        let socket = UdpSocket::bind(unspecified_local_addr(addr))?;
+        socket.connect(addr)?;
+        Ok(BlockingMaster::with_socket(socket, addr))
+    }
+
+    /// Bind a new socket on `local`, connected to the master at `addr`.
+    pub fn new_bound(addr: SocketAddr, local: SocketAddr) -> io::Result<BlockingMaster> {
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(BlockingMaster::with_socket(socket, addr))
+    }
+
+    /// Use `socket` as is, which is only connected to the master if the
+    /// caller did so, see [`BlockingMaster::connect`].
+    pub fn with_socket(socket: UdpSocket, addr: SocketAddr) -> BlockingMaster {
+        BlockingMaster::with_capacity(socket, addr, MAX_PACKET_LEN)
+    }
+
+    /// Use send and receive buffers of `packet_len` bytes instead of
+    /// [`MAX_PACKET_LEN`].
+    pub fn with_capacity(socket: UdpSocket, addr: SocketAddr, packet_len: usize) -> BlockingMaster {
+        BlockingMaster {
+            send_buf: Vec::with_capacity(packet_len),
+            // One more byte to tell a datagram of `packet_len` bytes from a
+            // truncated one.
+            recv_buf: vec![0; packet_len + 1],
+            connected: socket.peer_addr().is_ok_and(|peer| peer == addr),
+            socket,
+            addr,
+            game_socket: None,
+        }
+    }
+
+    /// Size of the largest datagram received without being truncated.
+    pub fn packet_len(&self) -> usize {
+        self.recv_buf.len() - 1
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Address of the master.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Talk to the master at `addr` from now on. Datagrams still in
+    /// flight from the previous address are ignored by the next request.
+    pub fn set_addr(&mut self, addr: SocketAddr) -> io::Result<()> {
+        if self.connected {
+            self.socket.connect(addr)?;
+        }
+        self.addr = addr;
+        Ok(())
+    }
+
+    /// Connect the socket to the master, so the operating system drops
+    /// datagrams from any other address. Sockets bound by
+    /// [`BlockingMaster::new`] and [`BlockingMaster::new_bound`] already
+    /// are.
+    ///
+    /// [`BlockingMaster::get_info`] and [`BlockingMaster::get_status`]
+    /// then go through a separate unconnected socket, to reach game
+    /// servers.
+    pub fn connect(&mut self) -> io::Result<()> {
+        self.socket.connect(self.addr)?;
+        self.connected = true;
+        Ok(())
+    }
+
+    /// Send the request in `send_buf` to the master.
+    fn send_request(&self) -> io::Result<()> {
+        if self.connected {
+            self.socket.send(&self.send_buf)?;
+        } else {
+            self.socket.send_to(&self.send_buf, self.addr)?;
+        }
+        Ok(())
+    }
+
+    /// Send a "getservers" and collect every "getserversResponse" until
+    /// an EOT is received or no datagram arrives within `timeout`. The
+    /// timeout restarts with each datagram, see
+    /// [`BlockingMaster::get_servers_with`] to also bound the total time,
+    /// resend the request or cap the number of servers. Addresses sent
+    /// more than once are only kept once.
+    ///
+    /// `eot` tells whether collection ended with an EOT rather than a
+    /// timeout. Fails with [`ClientError::Timeout`] if nothing arrived.
+    pub fn get_servers(
+        &mut self,
+        request: &GetServers,
+        timeout: Duration,
+    ) -> Result<GetServersResponse, ClientError> {
+        self.get_servers_with(request, &QueryConfig::with_timeout(timeout))
+            .map(|(response, _)| response)
+    }
+
+    /// Like [`BlockingMaster::get_servers`], but following `config`, and
+    /// also return statistics about the datagrams received after the last
+    /// send.
+    pub fn get_servers_with(
+        &mut self,
+        request: &GetServers,
+        config: &QueryConfig,
+    ) -> Result<(GetServersResponse, CollectionStats), ClientError> {
+        self.send_buf.clear();
+        request.write_all_to(&mut self.send_buf)?;
+
+        let (addresses, skipped, stats) = self.collect_with(config, parse_get_servers)?;
+        let response = GetServersResponse {
+            addresses,
+            eot: stats.had_eot,
+            skipped,
+        };
+        Ok((response, stats))
+    }
+
+    /// Send a "getserversExt" and collect every "getserversExtResponse"
+    /// until an EOT is received or no datagram arrives within `timeout`.
+    /// The timeout restarts with each datagram, see
+    /// [`BlockingMaster::get_servers_ext_with`] to also bound the total
+    /// time, resend the request or cap the number of servers. Addresses
+    /// sent more than once are only kept once.
+    ///
+    /// `datagram_info` is [`DatagramInfo::Eot`] if collection ended with
+    /// an EOT, [`DatagramInfo::Collected`] if it ended with a timeout.
+    /// Fails with [`ClientError::Timeout`] if nothing arrived.
+    pub fn get_servers_ext(
+        &mut self,
+        request: &GetServersExt,
+        timeout: Duration,
+    ) -> Result<GetServersExtResponse, ClientError> {
+        self.get_servers_ext_with(request, &QueryConfig::with_timeout(timeout))
+            .map(|(response, _)| response)
+    }
+
+    /// Like [`BlockingMaster::get_servers_ext`], but following `config`,
+    /// and also return statistics about the datagrams received after the
+    /// last send.
+    pub fn get_servers_ext_with(
+        &mut self,
+        request: &GetServersExt,
+        config: &QueryConfig,
+    ) -> Result<(GetServersExtResponse, CollectionStats), ClientError> {
+        self.send_buf.clear();
+        request.write_all_to(&mut self.send_buf)?;
+
+        let (addresses, _, stats) = self.collect_with(config, parse_get_servers_ext)?;
+        let response = GetServersExtResponse {
+            addresses,
+            datagram_info: collected_datagram_info(stats.had_eot),
+        };
+        Ok((response, stats))
+    }
+
+    /// Send a "getservers" and iterate over the addresses of every
+    /// "getserversResponse" as it arrives, until an EOT is received or no
+    /// datagram arrives within `timeout`.
+    pub fn get_servers_iter(
+        &mut self,
+        request: &GetServers,
+        timeout: Duration,
+    ) -> Result<Datagrams<'_, SocketAddrV4>, ClientError> {
+        self.send_buf.clear();
+        request.write_all_to(&mut self.send_buf)?;
+        self.send_request()?;
+        Ok(Datagrams::new(self, timeout, parse_get_servers))
+    }
+
+    /// Send a "getserversExt" and iterate over the addresses of every
+    /// "getserversExtResponse" as it arrives, until an EOT is received or
+    /// no datagram arrives within `timeout`.
+    pub fn get_servers_ext_iter(
+        &mut self,
+        request: &GetServersExt,
+        timeout: Duration,
+    ) -> Result<Datagrams<'_, SocketAddr>, ClientError> {
+        self.send_buf.clear();
+        request.write_all_to(&mut self.send_buf)?;
+        self.send_request()?;
+        Ok(Datagrams::new(self, timeout, parse_get_servers_ext))
+    }
+
+    /// Receive datagrams from the master until one contains an EOT or
+    /// `limits` are reached, appending the addresses not already in
+    /// `addresses` and counting the entries skipped in `skipped`.
+    /// Datagrams that can't be parsed are only counted in the statistics.
+    fn collect<A: Copy + Eq + Hash>(
+        &mut self,
+        limits: CollectLimits,
+        addresses: &mut Vec<A>,
+        skipped: &mut usize,
+        parse: ParseDatagram<A>,
+    ) -> Result<CollectionStats, ClientError> {
+        let mut stats = CollectionStats::default();
+        let mut seen: HashSet<A> = addresses.iter().copied().collect();
+        while let Some(written) = recv_from_addr(
+            &self.socket,
+            self.addr,
+            &mut self.recv_buf,
+            limits.next_timeout(stats.datagrams),
+        )? {
+            let Ok((datagram, eot, datagram_skipped)) = parse(&self.recv_buf[..written]) else {
+                stats.invalid += 1;
+                continue;
+            };
+            stats.datagrams += 1;
+            stats.bytes += written;
+            addresses.extend(datagram.into_iter().filter(|addr| seen.insert(*addr)));
+            *skipped += datagram_skipped;
+            stats.had_eot = eot;
+            if let Some(max_servers) = limits.max_servers {
+                if addresses.len() >= max_servers {
+                    addresses.truncate(max_servers);
+                    return Ok(stats);
+                }
+            }
+            if eot {
+                return Ok(stats);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Send the request in `send_buf` and collect the response following
+    /// `config`, resending for as long as no datagram arrives. Returns the
+    /// addresses, the number of entries skipped and the statistics of the
+    /// last attempt.
+    fn collect_with<A: Copy + Eq + Hash>(
+        &mut self,
+        config: &QueryConfig,
+        parse: ParseDatagram<A>,
+    ) -> Result<(Vec<A>, usize, CollectionStats), ClientError> {
+        let deadline = config.deadline.map(|deadline| Instant::now() + deadline);
+        let limits = config.collect_limits(deadline);
+        let mut addresses = vec![];
+        let mut skipped = 0;
+        for attempt in 0..=config.retries {
+            if attempt > 0 {
+                let mut delay = config.delay_before(attempt);
+                if let Some(deadline) = deadline {
+                    delay = delay.min(deadline.saturating_duration_since(Instant::now()));
+                }
+                thread::sleep(delay);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            self.send_request()?;
+            let stats = self.collect(limits, &mut addresses, &mut skipped, parse)?;
+            if stats.datagrams > 0 {
+                return Ok((addresses, skipped, stats));
+            }
+        }
+        Err(ClientError::Timeout)
+    }
+
+    /// Send a "getmotd" and wait up to `timeout` for the "motd".
+    pub fn get_motd(&mut self, challenge: &[u8], timeout: Duration) -> Result<Motd, ClientError> {
+        self.send_buf.clear();
+        GetMotd::new(Challenge::try_from(challenge)?).write_all_to(&mut self.send_buf)?;
+        self.send_request()?;
+
+        let written = recv_from_addr(&self.socket, self.addr, &mut self.recv_buf, timeout)?
+            .ok_or(ClientError::Timeout)?;
+        Motd::parse(&self.recv_buf[..written])
+            .map_err(|e| invalid_response(&self.recv_buf[..written], e))
+    }
+
+    /// Send a "getinfo" to the game server at `server`, as a master does to
+    /// validate a heartbeat, and wait up to `timeout` for its "infoResponse".
+    /// Fails with [`ClientError::ChallengeMismatch`] if the response doesn't
+    /// echo `challenge`.
+    pub fn get_info(
+        &mut self,
+        server: SocketAddr,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<InfoResponse, ClientError> {
+        self.send_buf.clear();
+        GetInfo::new(Challenge::try_from(challenge)?).write_all_to(&mut self.send_buf)?;
+        let socket = game_socket(&self.socket, self.connected, &mut self.game_socket, server)?;
+        socket.send_to(&self.send_buf, server)?;
+
+        let written = recv_from_addr(socket, server, &mut self.recv_buf, timeout)?
+            .ok_or(ClientError::Timeout)?;
+        let response = InfoResponse::parse(&self.recv_buf[..written])
+            .map_err(|e| invalid_response(&self.recv_buf[..written], e))?;
+        check_challenge(response, challenge)
+    }
+
+    /// Send a "getstatus" to the game server at `server` and wait up to
+    /// `timeout` for its "statusResponse".
+    pub fn get_status(
+        &mut self,
+        server: SocketAddr,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<StatusResponse, ClientError> {
+        self.send_buf.clear();
+        GetStatus::new(Challenge::try_from(challenge)?).write_all_to(&mut self.send_buf)?;
+        let socket = game_socket(&self.socket, self.connected, &mut self.game_socket, server)?;
+        socket.send_to(&self.send_buf, server)?;
+
+        let written = recv_from_addr(socket, server, &mut self.recv_buf, timeout)?
+            .ok_or(ClientError::Timeout)?;
+        StatusResponse::parse(&self.recv_buf[..written])
+            .map_err(|e| invalid_response(&self.recv_buf[..written], e))
+    }
+
+    /// Like [`BlockingMaster::get_servers`], but follows up with a
+    /// "getstatus" to every server and keeps only those whose status
+    /// matches `filter`, e.g. by their `gametype` key since masters don't
+    /// always honor the gametype of [`GetServersFilter`](crate::GetServersFilter).
+    ///
+    /// This requires one status query per server, each waiting up to
+    /// `timeout`. Servers that don't respond or respond with an invalid
+    /// status are skipped.
+    pub fn get_servers_with_status<F>(
+        &mut self,
+        request: &GetServers,
+        challenge: &[u8],
+        timeout: Duration,
+        mut filter: F,
+    ) -> Result<Vec<(SocketAddrV4, StatusResponse)>, ClientError>
+    where
+        F: FnMut(&StatusResponse) -> bool,
+    {
+        Challenge::try_from(challenge)?;
+        let servers = self.get_servers(request, timeout)?;
+        let mut matching = vec![];
+        for addr in servers.addresses {
+            match self.get_status(addr.into(), challenge, timeout) {
+                Ok(status) if filter(&status) => matching.push((addr, status)),
+                Ok(_)
+                | Err(
+                    ClientError::Timeout | ClientError::InvalidResponse(_) | ClientError::Truncated,
+                ) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(matching)
+    }
+
+    /// Like [`BlockingMaster::get_servers`], but follows up with a
+    /// "getinfo" and a "getstatus" to every server, each waiting up to
+    /// `timeout`, pairing each address with what the server answered.
+    ///
+    /// Servers that don't respond or respond with an invalid message are
+    /// still listed, with `None` in place of the missing responses.
+    pub fn browse(
+        &mut self,
+        request: &GetServers,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<ServerEntry>, ClientError> {
+        Challenge::try_from(challenge)?;
+        let servers = self.get_servers(request, timeout)?;
+        let mut entries = Vec::with_capacity(servers.addresses.len());
+        for addr in servers.addresses {
+            let addr = SocketAddr::V4(addr);
+            let sent_at = Instant::now();
+            let (info, rtt) = match skip_unanswered(self.get_info(addr, challenge, timeout))? {
+                Some(info) => (Some(info), Some(sent_at.elapsed())),
+                None => (None, None),
+            };
+            let status = skip_unanswered(self.get_status(addr, challenge, timeout))?;
+            entries.push(ServerEntry {
+                addr,
+                info,
+                status,
+                rtt,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/// The socket of a [`BlockingMaster`] to reach the game server at
+/// `server`: its own one unless it is connected, otherwise `game_socket`,
+/// bound again if its address family doesn't match the server's.
+fn game_socket<'a>(
+    socket: &'a UdpSocket,
+    connected: bool,
+    game_socket: &'a mut Option<UdpSocket>,
+    server: SocketAddr,
+) -> io::Result<&'a UdpSocket> {
+    if !connected {
+        return Ok(socket);
+    }
+    if let Some(socket) = game_socket {
+        if socket.local_addr()?.is_ipv4() != server.is_ipv4() {
+            *game_socket = None;
+        }
+    }
+    match game_socket {
+        Some(socket) => Ok(socket),
+        None => Ok(game_socket.insert(UdpSocket::bind(unspecified_local_addr(server))?)),
+    }
+}
+
+/// `None` for a server that didn't give a valid response, other errors are
+/// passed through.
+fn skip_unanswered<T>(result: Result<T, ClientError>) -> Result<Option<T>, ClientError> {
+    match result {
+        Ok(response) => Ok(Some(response)),
+        Err(
+            ClientError::Timeout
+            | ClientError::InvalidResponse(_)
+            | ClientError::Truncated
+            | ClientError::ChallengeMismatch,
+        ) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// A server listed by a master, with its responses, see
+/// [`BlockingMaster::browse`].
+#[derive(Debug, Clone)]
+pub struct ServerEntry {
+    pub addr: SocketAddr,
+    pub info: Option<InfoResponse>,
+    pub status: Option<StatusResponse>,
+    /// Round-trip time of the "getinfo".
+    pub rtt: Option<Duration>,
+}
+
+/// Iterator over the addresses of each datagram of a master response,
+/// see [`BlockingMaster::get_servers_iter`].
+///
+/// Ends after a datagram with an EOT, a timeout, or the first error.
+pub struct Datagrams<'a, A> {
+    master: &'a mut BlockingMaster,
+    timeout: Duration,
+    parse: ParseDatagram<A>,
+    done: bool,
+}
+
+impl<'a, A> Datagrams<'a, A> {
+    fn new(
+        master: &'a mut BlockingMaster,
+        timeout: Duration,
+        parse: ParseDatagram<A>,
+    ) -> Datagrams<'a, A> {
+        Datagrams {
+            master,
+            timeout,
+            parse,
+            done: false,
+        }
+    }
+}
+
+impl<A> Iterator for Datagrams<'_, A> {
+    type Item = Result<Vec<A>, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let master = &mut *self.master;
+        let datagram = recv_from_addr(
+            &master.socket,
+            master.addr,
+            &mut master.recv_buf,
+            self.timeout,
+        )
+        .and_then(|written| match written {
+            Some(written) => (self.parse)(&master.recv_buf[..written]).map(Some),
+            None => Ok(None),
+        });
+        match datagram {
+            Ok(Some((addresses, eot, _))) => {
+                self.done = eot;
+                Some(Ok(addresses))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Responses of several masters to the same "getservers", see
+/// [`query_masters`].
+#[derive(Debug)]
+pub struct MultiMasterResponse {
+    pub responses: HashMap<SocketAddr, Result<GetServersResponse, ClientError>>,
+}
+
+impl MultiMasterResponse {
+    /// Addresses returned by any master, sorted and each only once.
+    pub fn addresses(&self) -> Vec<SocketAddrV4> {
+        let mut addresses: Vec<SocketAddrV4> = self
+            .responses
+            .values()
+            .flatten()
+            .flat_map(|response| response.addresses.iter().copied())
+            .collect();
+        addresses.sort_unstable();
+        addresses.dedup();
+        addresses
+    }
+}
+
+/// Send `request` to every master in `masters` at once, each from its own
+/// socket and thread, and wait for all of them as in
+/// [`BlockingMaster::get_servers`].
+pub fn query_masters(
+    masters: &[SocketAddr],
+    request: &GetServers,
+    timeout: Duration,
+) -> MultiMasterResponse {
+    let responses = thread::scope(|scope| {
+        let handles: Vec<_> = masters
+            .iter()
+            .map(|&addr| {
+                scope.spawn(move || {
+                    let response = BlockingMaster::new(addr)
+                        .map_err(ClientError::from)
+                        .and_then(|mut master| master.get_servers(request, timeout));
+                    (addr, response)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("master query thread panicked"))
+            .collect()
+    });
+    MultiMasterResponse { responses }
+}
+
+/// A blocking client to a game server.
+pub struct BlockingGame {
+    socket: UdpSocket,
+    addr: SocketAddr,
+    send_buf: Vec<u8>,
+    recv_buf: Vec<u8>,
+    connected: bool,
+}
+
+impl BlockingGame {
+    /// Bind a new socket on `0.0.0.0:0`, or `[::]:0` if `addr` is IPv6,
+    /// to talk to the game server at `addr`.
+    pub fn new(addr: SocketAddr) -> io::Result<BlockingGame> {
+        let socket = UdpSocket::bind(unspecified_local_addr(addr))?;
+        Ok(BlockingGame::with_socket(socket, addr))
+    }
+
+    /// Bind a new socket on `local` to talk to the game server at `addr`.
+    pub fn new_bound(addr: SocketAddr, local: SocketAddr) -> io::Result<BlockingGame> {
+        let socket = UdpSocket::bind(local)?;
+        Ok(BlockingGame::with_socket(socket, addr))
+    }
+
+    pub fn with_socket(socket: UdpSocket, addr: SocketAddr) -> BlockingGame {
+        BlockingGame::with_capacity(socket, addr, MAX_PACKET_LEN)
+    }
+
+    /// Use send and receive buffers of `packet_len` bytes instead of
+    /// [`MAX_PACKET_LEN`].
+    pub fn with_capacity(socket: UdpSocket, addr: SocketAddr, packet_len: usize) -> BlockingGame {
+        BlockingGame {
+            socket,
+            addr,
+            send_buf: Vec::with_capacity(packet_len),
+            // One more byte to tell a datagram of `packet_len` bytes from a
+            // truncated one.
+            recv_buf: vec![0; packet_len + 1],
+            connected: false,
+        }
+    }
+
+    /// Size of the largest datagram received without being truncated.
+    pub fn packet_len(&self) -> usize {
+        self.recv_buf.len() - 1
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Address of the game server.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Talk to the game server at `addr` from now on. Datagrams still in
+    /// flight from the previous address are ignored by the next request.
+    pub fn set_addr(&mut self, addr: SocketAddr) -> io::Result<()> {
+        if self.connected {
+            self.socket.connect(addr)?;
+        }
+        self.addr = addr;
+        Ok(())
+    }
+
+    /// Connect the socket to the game server, so the operating system drops
+    /// datagrams from any other address.
+    pub fn connect(&mut self) -> io::Result<()> {
+        self.socket.connect(self.addr)?;
+        self.connected = true;
+        Ok(())
+    }
+
+    /// Send the request in `send_buf` to the game server.
+    fn send_request(&self) -> io::Result<()> {
+        if self.connected {
+            self.socket.send(&self.send_buf)?;
+        } else {
+            self.socket.send_to(&self.send_buf, self.addr)?;
+        }
+        Ok(())
+    }
+
+    /// Send a "getinfo" and wait up to `timeout` for the "infoResponse".
+    /// Fails with [`ClientError::InvalidChallenge`] if `challenge` isn't a
+    /// valid [`Challenge`], and with [`ClientError::ChallengeMismatch`] if
+    /// the response doesn't echo it.
+    pub fn get_info(
+        &mut self,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<InfoResponse, ClientError> {
+        self.get_info_timed(challenge, timeout)
+            .map(|(response, _)| response)
+    }
+
+    /// Like [`BlockingGame::get_info`], but with an already validated
+    /// challenge.
+    pub fn get_info_checked(
+        &mut self,
+        challenge: Challenge<'_>,
+        timeout: Duration,
+    ) -> Result<InfoResponse, ClientError> {
+        self.get_info_checked_with(challenge, &QueryConfig::with_timeout(timeout))
+            .map(|(response, _)| response)
+    }
+
+    /// Like [`BlockingGame::get_info`], but also return the round-trip
+    /// time, from just before the request is sent to the response arrival.
+    pub fn get_info_timed(
+        &mut self,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<(InfoResponse, Duration), ClientError> {
+        self.get_info_with(challenge, &QueryConfig::with_timeout(timeout))
+    }
+
+    /// Like [`BlockingGame::get_info_timed`], but following `config`. The
+    /// round-trip time is measured from the last send.
+    pub fn get_info_with(
+        &mut self,
+        challenge: &[u8],
+        config: &QueryConfig,
+    ) -> Result<(InfoResponse, Duration), ClientError> {
+        self.get_info_checked_with(Challenge::try_from(challenge)?, config)
+    }
+
+    fn get_info_checked_with(
+        &mut self,
+        challenge: Challenge<'_>,
+        config: &QueryConfig,
+    ) -> Result<(InfoResponse, Duration), ClientError> {
+        let request = GetInfo::new(challenge);
+        self.send_buf.clear();
+        request.write_all_to(&mut self.send_buf)?;
+
+        let (written, rtt) = self.request_with(config)?;
+        let response = InfoResponse::parse(&self.recv_buf[..written])
+            .map_err(|e| invalid_response(&self.recv_buf[..written], e))?;
+        Ok((check_challenge(response, &request.challenge)?, rtt))
+    }
+
+    /// Send a "getinfo" for each of `challenges` at once and wait up to
+    /// `timeout` in total for their "infoResponse", matched back to their
+    /// request by the `challenge` key they echo. The results are in the
+    /// order of `challenges`.
+    ///
+    /// Responses that can't be parsed or that echo none of the challenges
+    /// are ignored, requests left unanswered fail with
+    /// [`ClientError::Timeout`], or with a copy of the socket error that
+    /// stopped the wait.
+    ///
+    /// A challenge listed more than once is sent once per entry, and each
+    /// response echoing it goes to the first of these entries still
+    /// waiting, so an entry only gets an answer if the server answers each
+    /// request.
+    pub fn get_info_many(
+        &mut self,
+        challenges: &[&[u8]],
+        timeout: Duration,
+    ) -> Vec<Result<InfoResponse, ClientError>> {
+        let mut results: Vec<Result<InfoResponse, ClientError>> = challenges
+            .iter()
+            .map(|&challenge| {
+                self.send_buf.clear();
+                GetInfo::new(Challenge::try_from(challenge)?).write_all_to(&mut self.send_buf)?;
+                self.send_request()?;
+                Err(ClientError::Timeout)
+            })
+            .collect();
+
+        let deadline = Instant::now() + timeout;
+        let is_pending =
+            |result: &Result<_, ClientError>| matches!(result, Err(ClientError::Timeout));
+        while results.iter().any(is_pending) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let written =
+                match recv_from_addr(&self.socket, self.addr, &mut self.recv_buf, remaining) {
+                    Ok(Some(written)) => written,
+                    Ok(None) => break,
+                    Err(ClientError::Io(e)) => {
+                        for result in results.iter_mut().filter(|result| is_pending(result)) {
+                            *result = Err(io::Error::new(e.kind(), e.to_string()).into());
+                        }
+                        break;
+                    }
+                    Err(_) => continue,
+                };
+            let Ok(response) = InfoResponse::parse(&self.recv_buf[..written]) else {
+                continue;
+            };
+            let Some(index) = challenges
+                .iter()
+                .zip(&results)
+                .position(|(&challenge, result)| {
+                    is_pending(result) && response.get(b"challenge") == Some(challenge)
+                })
+            else {
+                continue;
+            };
+            results[index] = Ok(response);
+        }
+        results
+    }
+
+    /// Send a "getstatus" and wait up to `timeout` for the "statusResponse".
+    pub fn get_status(
+        &mut self,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<StatusResponse, ClientError> {
+        self.get_status_timed(challenge, timeout)
+            .map(|(response, _)| response)
+    }
+
+    /// Like [`BlockingGame::get_status`], but also return the round-trip
+    /// time, from just before the request is sent to the response arrival.
+    pub fn get_status_timed(
+        &mut self,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<(StatusResponse, Duration), ClientError> {
+        self.get_status_with(challenge, &QueryConfig::with_timeout(timeout))
+    }
+
+    /// Like [`BlockingGame::get_status_timed`], but following `config`.
+    /// The round-trip time is measured from the last send.
+    pub fn get_status_with(
+        &mut self,
+        challenge: &[u8],
+        config: &QueryConfig,
+    ) -> Result<(StatusResponse, Duration), ClientError> {
+        self.send_buf.clear();
+        GetStatus::new(Challenge::try_from(challenge)?).write_all_to(&mut self.send_buf)?;
+
+        let (written, rtt) = self.request_with(config)?;
+        let response = StatusResponse::parse(&self.recv_buf[..written])
+            .map_err(|e| invalid_response(&self.recv_buf[..written], e))?;
+        Ok((response, rtt))
+    }
+
+    /// Send the request in `send_buf` and receive the response into
+    /// `recv_buf`, resending following `config` while none arrives.
+    /// Returns its size and the time since the last send.
+    fn request_with(&mut self, config: &QueryConfig) -> Result<(usize, Duration), ClientError> {
+        let deadline = config.deadline.map(|deadline| Instant::now() + deadline);
+        let remaining = |timeout: Duration| match deadline {
+            Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => timeout,
+        };
+        for attempt in 0..=config.retries {
+            if attempt > 0 {
+                thread::sleep(remaining(config.delay_before(attempt)));
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            let sent_at = Instant::now();
+            self.send_request()?;
+            let timeout = remaining(config.timeout);
+            if let Some(written) =
+                recv_from_addr(&self.socket, self.addr, &mut self.recv_buf, timeout)?
+            {
+                return Ok((written, sent_at.elapsed()));
+            }
+        }
+        Err(ClientError::Timeout)
+    }
+
+    /// Send a "getstatus" from the shared `socket` to the game server at
+    /// `addr` and wait up to `timeout` for its "statusResponse", so many
+    /// servers can be queried one after another without a socket each.
+    ///
+    /// Datagrams from other addresses are discarded while waiting, so the
+    /// socket shouldn't be used by several queries at the same time.
+    pub fn get_status_on(
+        socket: &UdpSocket,
+        addr: SocketAddr,
+        challenge: &[u8],
+        timeout: Duration,
+    ) -> Result<StatusResponse, ClientError> {
+        let mut send_buf = Vec::with_capacity(MAX_PACKET_LEN);
+        GetStatus::new(Challenge::try_from(challenge)?).write_all_to(&mut send_buf)?;
+        socket.send_to(&send_buf, addr)?;
+
+        let mut recv_buf = vec![0; MAX_PACKET_LEN + 1];
+        let written =
+            recv_from_addr(socket, addr, &mut recv_buf, timeout)?.ok_or(ClientError::Timeout)?;
+        StatusResponse::parse(&recv_buf[..written])
+            .map_err(|e| invalid_response(&recv_buf[..written], e))
+    }
+
+    /// Send a "getchallenge" and wait up to `timeout` for the
+    /// "challengeResponse", to check whether the server would accept a
+    /// connection. No "connect" is sent, so the server isn't joined.
+    pub fn probe_connect(&mut self, timeout: Duration) -> Result<ProbeResult, ClientError> {
+        self.send_buf.clear();
+        GetChallenge.write_all_to(&mut self.send_buf)?;
+        self.send_request()?;
+
+        let Some(written) = recv_from_addr(&self.socket, self.addr, &mut self.recv_buf, timeout)?
+        else {
+            return Ok(ProbeResult::Timeout);
+        };
+        let bytes = &self.recv_buf[..written];
+        if let Ok(print) = Print::parse(bytes) {
+            return Ok(ProbeResult::Rejected(
+                String::from_utf8_lossy(&print.text).trim_end().to_owned(),
+            ));
+        }
+        ChallengeResponse::parse(bytes)
+            .map(ProbeResult::Accepted)
+            .map_err(|e| invalid_response(bytes, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProtocolVersion;
+    use std::{borrow::Cow, thread::JoinHandle};
+
+    /// A server on the loopback that answers the `n`th request it receives
+    /// with the datagrams of `replies[n]`, and returns how many requests it
+    /// received.
+    fn mock_server(replies: Vec<Vec<Vec<u8>>>) -> (SocketAddr, JoinHandle<usize>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let addr = socket.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let mut buf = [0; MAX_PACKET_LEN];
+            let mut requests = 0;
+            for datagrams in replies {
+                let Ok((_, from)) = socket.recv_from(&mut buf) else {
+                    break;
+                };
+                requests += 1;
+                for datagram in datagrams {
+                    socket.send_to(&datagram, from).unwrap();
+                }
+            }
+            requests
+        });
+        (addr, handle)
+    }
+
+    fn get_servers_request() -> GetServers<'static> {
+        GetServers {
+            game_name: None,
+            protocol_version: ProtocolVersion::new(Cow::Borrowed(b"68")).unwrap(),
+            filter: Default::default(),
+        }
+    }
+
+    fn servers_datagram(addresses: &[&str], eot: bool) -> Vec<u8> {
+        let response = GetServersResponse {
+            addresses: addresses.iter().map(|a| a.parse().unwrap()).collect(),
+            eot,
+            skipped: 0,
+        };
+        let mut datagram = vec![];
+        response.write_all_to(&mut datagram).unwrap();
+        datagram
+    }
+
+    fn quick_retries(retries: u32) -> QueryConfig {
+        QueryConfig {
+            retries,
+            backoff: Duration::from_millis(10),
+            ..QueryConfig::with_timeout(Duration::from_millis(200))
+        }
+    }
+
+    #[test]
+    fn get_servers_with_resends_after_a_lost_response() {
+        let (addr, server) = mock_server(vec![
+            vec![],
+            vec![servers_datagram(&["1.2.3.4:26000"], true)],
+        ]);
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let (response, stats) = master
+            .get_servers_with(&get_servers_request(), &quick_retries(1))
+            .unwrap();
+        assert_eq!(response.addresses, ["1.2.3.4:26000".parse().unwrap()]);
+        assert!(response.eot);
+        assert_eq!(stats.datagrams, 1);
+        assert!(stats.had_eot);
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn get_servers_with_stops_at_max_servers() {
+        let (addr, server) = mock_server(vec![vec![
+            servers_datagram(&["1.1.1.1:1", "2.2.2.2:2"], false),
+            servers_datagram(&["3.3.3.3:3", "4.4.4.4:4"], false),
+            servers_datagram(&["5.5.5.5:5"], true),
+        ]]);
+        let config = QueryConfig {
+            max_servers: Some(3),
+            ..QueryConfig::with_timeout(Duration::from_millis(200))
+        };
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let (response, stats) = master
+            .get_servers_with(&get_servers_request(), &config)
+            .unwrap();
+        assert_eq!(response.addresses.len(), 3);
+        assert_eq!(stats.datagrams, 2);
+        assert!(!stats.had_eot);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn get_servers_with_gives_up_at_the_deadline() {
+        let (addr, _) = mock_server(vec![vec![]; 100]);
+        let config = QueryConfig {
+            deadline: Some(Duration::from_millis(150)),
+            ..quick_retries(100)
+        };
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let started = Instant::now();
+        let result = master.get_servers_with(&get_servers_request(), &config);
+        assert!(matches!(result, Err(ClientError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn get_info_with_resends_after_a_lost_response() {
+        let (addr, server) = mock_server(vec![
+            vec![],
+            vec![b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\abc\\sv_hostname\\test".to_vec()],
+        ]);
+        let mut game = BlockingGame::new(addr).unwrap();
+        let (response, rtt) = game.get_info_with(b"abc", &quick_retries(1)).unwrap();
+        assert_eq!(response.get(b"sv_hostname"), Some(b"test".as_slice()));
+        assert!(rtt < Duration::from_millis(200));
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn get_info_rejects_a_mismatched_challenge() {
+        let (addr, server) = mock_server(vec![vec![
+            b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\stale\\sv_hostname\\test".to_vec(),
+        ]]);
+        let mut game = BlockingGame::new(addr).unwrap();
+        let result = game.get_info(b"abc", Duration::from_millis(200));
+        assert!(matches!(result, Err(ClientError::ChallengeMismatch)));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn get_info_detects_a_datagram_longer_than_packet_len() {
+        let info_response = |len: usize| {
+            let mut datagram =
+                b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\abc\\sv_hostname\\".to_vec();
+            datagram.resize(len, b'x');
+            datagram
+        };
+        let (addr, server) = mock_server(vec![vec![info_response(65)], vec![info_response(64)]]);
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut game = BlockingGame::with_capacity(socket, addr, 64);
+        let result = game.get_info(b"abc", Duration::from_millis(200));
+        assert!(matches!(result, Err(ClientError::Truncated)));
+        let response = game.get_info(b"abc", Duration::from_millis(200)).unwrap();
+        assert_eq!(response.get(b"sv_hostname").map(<[u8]>::len), Some(20));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn get_servers_skips_an_invalid_datagram() {
+        let (addr, server) = mock_server(vec![vec![
+            b"junk".to_vec(),
+            servers_datagram(&["1.2.3.4:26000"], true),
+        ]]);
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let (response, stats) = master
+            .get_servers_with(
+                &get_servers_request(),
+                &QueryConfig::with_timeout(Duration::from_millis(200)),
+            )
+            .unwrap();
+        assert_eq!(response.addresses, ["1.2.3.4:26000".parse().unwrap()]);
+        assert!(response.eot);
+        assert_eq!(stats.datagrams, 1);
+        assert_eq!(stats.invalid, 1);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn get_servers_accumulates_datagrams_until_eot() {
+        let (addr, server) = mock_server(vec![vec![
+            servers_datagram(&["1.1.1.1:1", "2.2.2.2:2", "3.3.3.3:3"], false),
+            servers_datagram(&["4.4.4.4:4"], true),
+            servers_datagram(&["5.5.5.5:5"], true),
+        ]]);
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let (response, stats) = master
+            .get_servers_with(
+                &get_servers_request(),
+                &QueryConfig::with_timeout(Duration::from_millis(200)),
+            )
+            .unwrap();
+        let expected: Vec<SocketAddrV4> = ["1.1.1.1:1", "2.2.2.2:2", "3.3.3.3:3", "4.4.4.4:4"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        assert_eq!(response.addresses, expected);
+        assert!(response.eot);
+        assert_eq!(stats.datagrams, 2);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn delay_before_doubles_the_backoff_and_adds_jitter() {
+        let config = QueryConfig {
+            backoff: Duration::from_millis(100),
+            jitter: Duration::from_millis(50),
+            ..QueryConfig::with_timeout(Duration::from_secs(1))
+        };
+        let mut delays = std::collections::HashSet::new();
+        for _ in 0..100 {
+            let first = config.delay_before(1);
+            assert!(first >= Duration::from_millis(100) && first <= Duration::from_millis(150));
+            let third = config.delay_before(3);
+            assert!(third >= Duration::from_millis(400) && third <= Duration::from_millis(450));
+            delays.insert(first);
+        }
+        assert!(delays.len() > 1, "jitter should vary between resends");
+
+        let config = QueryConfig {
+            jitter: Duration::ZERO,
+            ..config
+        };
+        assert_eq!(config.delay_before(2), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn get_info_with_waits_for_the_backoff_before_resending() {
+        let (addr, server) = mock_server(vec![
+            vec![],
+            vec![b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\abc".to_vec()],
+        ]);
+        let config = QueryConfig {
+            retries: 1,
+            backoff: Duration::from_millis(100),
+            jitter: Duration::from_millis(50),
+            ..QueryConfig::with_timeout(Duration::from_millis(50))
+        };
+        let mut game = BlockingGame::new(addr).unwrap();
+        let started = Instant::now();
+        game.get_info_with(b"abc", &config).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn get_info_checked_and_unchecked_challenges() {
+        let info_response = || vec![b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\abc".to_vec()];
+        let (addr, server) = mock_server(vec![info_response(), info_response()]);
+        let mut game = BlockingGame::new(addr).unwrap();
+        let timeout = Duration::from_millis(200);
+        let challenge = Challenge::try_from("abc").unwrap();
+        assert!(game.get_info_checked(challenge, timeout).is_ok());
+        assert!(matches!(
+            game.get_info(b"a b", timeout),
+            Err(ClientError::InvalidChallenge(_))
+        ));
+        assert!(game.get_info(b"abc", timeout).is_ok());
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn browse_queries_every_listed_server() {
+        let (answering, answering_server) = mock_server(vec![
+            vec![b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\abc\\hostname\\A".to_vec()],
+            vec![b"\xFF\xFF\xFF\xFFstatusResponse\n\\challenge\\abc\n0 50 \"Player\"\n".to_vec()],
+        ]);
+        let (silent, silent_server) = mock_server(vec![vec![], vec![]]);
+        let (addr, master_server) = mock_server(vec![vec![servers_datagram(
+            &[&answering.to_string(), &silent.to_string()],
+            true,
+        )]]);
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let entries = master
+            .browse(&get_servers_request(), b"abc", Duration::from_millis(200))
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].addr, answering);
+        let info = entries[0].info.as_ref().unwrap();
+        assert_eq!(info.key_values.hostname(), Some(&b"A"[..]));
+        assert!(entries[0].rtt.is_some());
+        let status = entries[0].status.as_ref().unwrap();
+        assert_eq!(status.player_infos.len(), 1);
+        assert_eq!(status.player_infos[0].ping, 50);
+
+        assert_eq!(entries[1].addr, silent);
+        assert!(entries[1].info.is_none());
+        assert!(entries[1].rtt.is_none());
+        assert!(entries[1].status.is_none());
+
+        assert_eq!(master_server.join().unwrap(), 1);
+        assert_eq!(answering_server.join().unwrap(), 2);
+        assert_eq!(silent_server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn new_connects_to_the_master_and_still_reaches_game_servers() {
+        let (game, game_server) = mock_server(vec![vec![
+            b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\abc".to_vec(),
+        ]]);
+        let (addr, master_server) =
+            mock_server(vec![vec![servers_datagram(&["1.2.3.4:26000"], true)]]);
+        let mut master = BlockingMaster::new(addr).unwrap();
+        assert_eq!(master.socket().peer_addr().unwrap(), addr);
+
+        let local_port = master.socket().local_addr().unwrap().port();
+        let spoofer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        spoofer
+            .send_to(
+                &servers_datagram(&["6.6.6.6:1"], true),
+                ("127.0.0.1", local_port),
+            )
+            .unwrap();
+        let socket = master.socket();
+        socket
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        assert!(is_timeout(&socket.recv(&mut [0; 64]).unwrap_err()));
+        let timeout = Duration::from_millis(200);
+        let response = master.get_servers(&get_servers_request(), timeout).unwrap();
+        assert_eq!(response.addresses, ["1.2.3.4:26000".parse().unwrap()]);
+
+        let info = master.get_info(game, b"abc", timeout).unwrap();
+        assert_eq!(info.get(b"challenge"), Some(&b"abc"[..]));
+        assert_eq!(master_server.join().unwrap(), 1);
+        assert_eq!(game_server.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn get_servers_with_counts_distinct_addresses_towards_max_servers() {
+        let (addr, server) = mock_server(vec![vec![
+            servers_datagram(&["1.1.1.1:1", "2.2.2.2:2", "1.1.1.1:1"], false),
+            servers_datagram(&["2.2.2.2:2", "3.3.3.3:3"], false),
+            servers_datagram(&["4.4.4.4:4"], true),
+        ]]);
+        let config = QueryConfig {
+            max_servers: Some(3),
+            ..QueryConfig::with_timeout(Duration::from_millis(200))
+        };
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let (response, stats) = master
+            .get_servers_with(&get_servers_request(), &config)
+            .unwrap();
+        assert_eq!(
+            response.addresses,
+            ["1.1.1.1:1", "2.2.2.2:2", "3.3.3.3:3"].map(|a| a.parse().unwrap())
+        );
+        assert_eq!(stats.datagrams, 2);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn master_get_info_answers_or_times_out() {
+        let (game, game_server) = mock_server(vec![vec![
+            b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\abc\\clients\\3".to_vec(),
+        ]]);
+        let (silent, silent_server) = mock_server(vec![vec![]]);
+        let (addr, _) = mock_server(vec![]);
+        let mut master = BlockingMaster::new(addr).unwrap();
+        let timeout = Duration::from_millis(200);
+
+        let info = master.get_info(game, b"abc", timeout).unwrap();
+        assert_eq!(info.clients(), Some(3));
+
+        let sent_at = Instant::now();
+        assert!(matches!(
+            master.get_info(silent, b"abc", timeout),
+            Err(ClientError::Timeout)
+        ));
+        assert!(sent_at.elapsed() >= timeout);
+        assert_eq!(game_server.join().unwrap(), 1);
+        assert_eq!(silent_server.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn get_info_many_matches_responses_answered_out_of_order() {
+        let info = |challenge: &[u8]| {
+            let mut datagram = b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\".to_vec();
+            datagram.extend_from_slice(challenge);
+            datagram.extend_from_slice(b"\\sv_hostname\\");
+            datagram.extend_from_slice(challenge);
+            datagram
+        };
+        let (addr, server) = mock_server(vec![
+            vec![],
+            vec![],
+            vec![info(b"b"), info(b"a"), info(b"unknown")],
+        ]);
+        let mut game = BlockingGame::new(addr).unwrap();
+        let results = game.get_info_many(&[b"a", b"b", b"c"], Duration::from_millis(200));
+        let hostname = |i: usize| results[i].as_ref().unwrap().get(b"sv_hostname");
+        assert_eq!(hostname(0), Some(b"a".as_slice()));
+        assert_eq!(hostname(1), Some(b"b".as_slice()));
+        assert!(matches!(results[2], Err(ClientError::Timeout)));
+        assert_eq!(server.join().unwrap(), 3);
+    }
+
+    #[test]
+    fn get_info_many_answers_duplicate_challenges_in_turn() {
+        let datagram = b"\xFF\xFF\xFF\xFFinfoResponse\n\\challenge\\a\\sv_hostname\\test".to_vec();
+        let (addr, server) = mock_server(vec![vec![datagram], vec![]]);
+        let mut game = BlockingGame::new(addr).unwrap();
+        let results = game.get_info_many(&[b"a", b"a"], Duration::from_millis(200));
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(ClientError::Timeout)));
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn get_status_on_queries_two_servers_on_one_socket() {
+        let status = |hostname: &[u8]| {
+            let mut datagram = b"\xFF\xFF\xFF\xFFstatusResponse\n\\sv_hostname\\".to_vec();
+            datagram.extend_from_slice(hostname);
+            datagram.extend_from_slice(b"\n0 50 \"player\"\n");
+            datagram
+        };
+        // The second reply of `a` is still queued when `b` is queried, and
+        // must be skipped as it comes from another address.
+        let (a, server_a) = mock_server(vec![vec![status(b"a"), status(b"late")]]);
+        let (b, server_b) = mock_server(vec![vec![status(b"b")]]);
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let timeout = Duration::from_millis(500);
+
+        let response = BlockingGame::get_status_on(&socket, a, b"abc", timeout).unwrap();
+        assert_eq!(
+            response.key_values.get(b"sv_hostname"),
+            Some(b"a".as_slice())
+        );
+        let response = BlockingGame::get_status_on(&socket, b, b"abc", timeout).unwrap();
+        assert_eq!(
+            response.key_values.get(b"sv_hostname"),
+            Some(b"b".as_slice())
+        );
+        assert_eq!(response.player_infos.len(), 1);
+        assert_eq!(server_a.join().unwrap(), 1);
+        assert_eq!(server_b.join().unwrap(), 1);
+    }
+}
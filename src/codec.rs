@@ -0,0 +1,161 @@
+//! A [`tokio_util::codec`] codec, to drive the protocol over a
+//! `tokio_util::udp::UdpFramed`.
+
+use crate::{
+    GetChallenge, GetInfo, GetMotd, GetServers, GetServersExt, GetServersExtResponse,
+    GetServersResponse, GetStatus, HeartBeat, Message, ParseResponseError, Rcon,
+};
+use bytes::BytesMut;
+use std::io;
+use thiserror::Error;
+use tokio_util::codec::{Decoder, Encoder};
+
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    InvalidMessage(#[from] ParseResponseError),
+}
+
+/// Decodes each datagram into a [`Message`], and encodes messages and
+/// requests into datagrams.
+///
+/// A datagram that can't be parsed is returned as
+/// [`CodecError::InvalidMessage`]; a `UdpFramed` keeps receiving after it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DpmasterCodec;
+
+impl DpmasterCodec {
+    pub fn new() -> DpmasterCodec {
+        DpmasterCodec
+    }
+}
+
+impl Decoder for DpmasterCodec {
+    type Item = Message<'static>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Message<'static>>, CodecError> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let datagram = src.split();
+        Ok(Some(Message::parse(&datagram)?.into_owned()))
+    }
+}
+
+/// Fails with [`io::ErrorKind::Unsupported`] for the responses that can
+/// only be parsed, such as "infoResponse" or "statusResponse".
+impl Encoder<Message<'_>> for DpmasterCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Message<'_>, dst: &mut BytesMut) -> io::Result<()> {
+        match item {
+            Message::GetInfo(m) => m.write_to_buf(dst),
+            Message::GetStatus(m) => m.write_to_buf(dst),
+            Message::GetChallenge(m) => m.write_to_buf(dst),
+            Message::HeartBeat(m) => m.write_to_buf(dst),
+            Message::GetServers(m) => m.write_to_buf(dst),
+            Message::GetServersResponse(m) => m.write_to_buf(dst),
+            Message::GetServersExt(m) => m.write_to_buf(dst),
+            Message::GetServersExtResponse(m) => m.write_to_buf(dst),
+            Message::GetMotd(m) => m.write_to_buf(dst),
+            Message::InfoResponse(_)
+            | Message::StatusResponse(_)
+            | Message::ChallengeResponse(_)
+            | Message::Motd(_)
+            | Message::Print(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "This message can't be encoded",
+            )),
+        }
+        .map(|_| ())
+    }
+}
+
+/// Encode requests that aren't a [`Message`] variant, by reference.
+macro_rules! impl_encoder {
+    ($($message:ty),* $(,)?) => {
+        $(
+            impl Encoder<&$message> for DpmasterCodec {
+                type Error = io::Error;
+
+                fn encode(&mut self, item: &$message, dst: &mut BytesMut) -> io::Result<()> {
+                    item.write_to_buf(dst).map(|_| ())
+                }
+            }
+        )*
+    };
+}
+
+impl_encoder!(
+    GetInfo<'_>,
+    GetStatus<'_>,
+    GetChallenge,
+    HeartBeat<'_>,
+    GetServers<'_>,
+    GetServersResponse,
+    GetServersExt<'_>,
+    GetServersExtResponse,
+    GetMotd<'_>,
+    Rcon<'_>,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameName, GetServersFilter, ProtocolVersion};
+    use futures::{SinkExt, StreamExt};
+    use std::net::SocketAddrV4;
+    use tokio::net::UdpSocket;
+    use tokio_util::udp::UdpFramed;
+
+    #[tokio::test]
+    async fn udp_framed_loopback() {
+        let master = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let master_addr = master.local_addr().unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        let mut master = UdpFramed::new(master, DpmasterCodec::new());
+        let mut client = UdpFramed::new(client, DpmasterCodec::new());
+
+        let request = GetServers {
+            game_name: Some(GameName::try_from("Xonotic").unwrap()),
+            protocol_version: ProtocolVersion::try_from("3").unwrap(),
+            filter: GetServersFilter::all(),
+        };
+        client
+            .get_ref()
+            .send_to(b"junk", master_addr)
+            .await
+            .unwrap();
+        client.send((&request, master_addr)).await.unwrap();
+
+        let junk = master.next().await.unwrap();
+        assert!(matches!(junk, Err(CodecError::InvalidMessage(_))));
+        let (message, from) = master.next().await.unwrap().unwrap();
+        assert_eq!(from, client_addr);
+        let Message::GetServers(received) = message else {
+            panic!("expected a getservers, got {message:?}");
+        };
+        assert_eq!(received.game_name.unwrap().as_ref(), b"Xonotic");
+        assert!(received.filter.empty && received.filter.full);
+
+        let response = GetServersResponse {
+            addresses: vec![SocketAddrV4::new([10, 0, 0, 1].into(), 26000)],
+            eot: true,
+            skipped: 0,
+        };
+        master
+            .send((Message::GetServersResponse(response.clone()), from))
+            .await
+            .unwrap();
+        let (message, from) = client.next().await.unwrap().unwrap();
+        assert_eq!(from, master_addr);
+        let Message::GetServersResponse(received) = message else {
+            panic!("expected a getserversResponse, got {message:?}");
+        };
+        assert_eq!(received, response);
+    }
+}
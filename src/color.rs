@@ -0,0 +1,137 @@
+//! Quake-style color codes in rich text such as server and player names.
+//!
+//! `^0` to `^9` select one of the ten standard colors, DarkPlaces also has
+//! `^xRGB` with three hex digits, and `^^` is an escaped literal `^`.
+
+use alloc::{borrow::Cow, vec, vec::Vec};
+
+/// Length of the color code starting at `input[0]`, if any.
+fn color_code_len(input: &[u8]) -> Option<usize> {
+    match input {
+        [b'^', b'0'..=b'9', ..] => Some(2),
+        [b'^', b'x', r, g, b, ..]
+            if r.is_ascii_hexdigit() && g.is_ascii_hexdigit() && b.is_ascii_hexdigit() =>
+        {
+            Some(5)
+        }
+        _ => None,
+    }
+}
+
+/// Remove color codes from `input` and unescape `^^` into `^`.
+///
+/// A lone `^` not starting a color code is kept as is.
+/// Returns [`Cow::Borrowed`] when there was nothing to remove.
+pub fn strip_color_codes(input: &[u8]) -> Cow<'_, [u8]> {
+    if !input.contains(&b'^') {
+        return Cow::Borrowed(input);
+    }
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i..].starts_with(b"^^") {
+            output.push(b'^');
+            i += 2;
+        } else if let Some(len) = color_code_len(&input[i..]) {
+            i += len;
+        } else {
+            output.push(input[i]);
+            i += 1;
+        }
+    }
+    if output.len() == input.len() {
+        Cow::Borrowed(input)
+    } else {
+        Cow::Owned(output)
+    }
+}
+
+/// Split `input` into runs of text tagged with their active color index
+/// (`0` to `9`), or `None` for text before any color code.
+///
+/// Every color code starts a new run, so consecutive codes produce empty
+/// runs, and the last one applies to the following text. An initial
+/// uncolored run is only produced when it isn't empty. `^xRGB` colors have
+/// no index, their runs are tagged `None`. An escaped `^^` ends the run
+/// after its first `^` and continues with the same color after the second.
+pub fn parse_color_segments(input: &[u8]) -> Vec<(Option<u8>, &[u8])> {
+    let mut segments = vec![];
+    let mut color = None;
+    let mut seen_code = false;
+    let mut start = 0;
+    let mut i = 0;
+    while i < input.len() {
+        if input[i..].starts_with(b"^^") {
+            segments.push((color, &input[start..i + 1]));
+            i += 2;
+            start = i;
+        } else if let Some(len) = color_code_len(&input[i..]) {
+            if start != i || seen_code {
+                segments.push((color, &input[start..i]));
+            }
+            seen_code = true;
+            color = match input[i + 1] {
+                digit @ b'0'..=b'9' => Some(digit - b'0'),
+                _ => None,
+            };
+            i += len;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    if start != input.len() || seen_code {
+        segments.push((color, &input[start..]));
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_color_codes_removes_nested_codes() {
+        assert_eq!(strip_color_codes(b"^1^2^3name"), b"name".as_slice());
+        assert_eq!(strip_color_codes(b"^1re^x0F0d^7"), b"red".as_slice());
+        assert_eq!(strip_color_codes(b"^^1"), b"^1".as_slice());
+        assert_eq!(strip_color_codes(b"^^^1a"), b"^a".as_slice());
+    }
+
+    #[test]
+    fn strip_color_codes_keeps_literal_and_trailing_carets() {
+        assert_eq!(strip_color_codes(b"a^^b"), b"a^b".as_slice());
+        assert_eq!(strip_color_codes(b"name^"), b"name^".as_slice());
+        assert_eq!(strip_color_codes(b"^1name^"), b"name^".as_slice());
+        assert_eq!(strip_color_codes(b"^x12"), b"^x12".as_slice());
+        assert_eq!(strip_color_codes(b"^"), b"^".as_slice());
+        assert!(matches!(strip_color_codes(b"name^"), Cow::Borrowed(_)));
+        assert!(matches!(strip_color_codes(b"plain"), Cow::Borrowed(_)));
+        assert!(matches!(strip_color_codes(b"^1name"), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn parse_color_segments_of_multi_color_names() {
+        let segments: &[(Option<u8>, &[u8])] = &[(Some(1), b"Red"), (Some(2), b"Green")];
+        assert_eq!(parse_color_segments(b"^1Red^2Green"), segments);
+        let segments: &[(Option<u8>, &[u8])] = &[(None, b"ab"), (Some(4), b"c"), (Some(7), b"d")];
+        assert_eq!(parse_color_segments(b"ab^4c^7d"), segments);
+        let segments: &[(Option<u8>, &[u8])] = &[(Some(1), b"a^"), (Some(1), b"b")];
+        assert_eq!(parse_color_segments(b"^1a^^b"), segments);
+        let segments: &[(Option<u8>, &[u8])] = &[(Some(3), b"x"), (None, b"y")];
+        assert_eq!(parse_color_segments(b"^3x^xF00y"), segments);
+        let segments: &[(Option<u8>, &[u8])] = &[(None, b"plain")];
+        assert_eq!(parse_color_segments(b"plain"), segments);
+        assert!(parse_color_segments(b"").is_empty());
+    }
+
+    #[test]
+    fn parse_color_segments_of_leading_and_consecutive_codes() {
+        let segments: &[(Option<u8>, &[u8])] = &[(Some(3), b"abc")];
+        assert_eq!(parse_color_segments(b"^3abc"), segments);
+        let segments: &[(Option<u8>, &[u8])] = &[(Some(1), b""), (Some(2), b"x")];
+        assert_eq!(parse_color_segments(b"^1^2x"), segments);
+        let segments: &[(Option<u8>, &[u8])] = &[(None, b"a"), (Some(5), b"")];
+        assert_eq!(parse_color_segments(b"a^5"), segments);
+    }
+}
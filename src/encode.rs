@@ -0,0 +1,123 @@
+//! Encoders of the binary parts of messages, the counterpart of the
+//! parsers in `parse`.
+
+use crate::BufferTooSmallError;
+use alloc::vec::Vec;
+use core::{
+    convert::Infallible,
+    net::{SocketAddr, SocketAddrV4, SocketAddrV6},
+};
+
+/// Where the encoders write, so they work with or without `std`.
+pub trait Sink {
+    type Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    type Error = S::Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), S::Error> {
+        (**self).write_all(bytes)
+    }
+}
+
+impl Sink for Vec<u8> {
+    type Error = Infallible;
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Infallible> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Writes at the start of a slice, failing once it is full.
+pub struct SliceSink<'a>(pub &'a mut [u8]);
+
+impl Sink for SliceSink<'_> {
+    type Error = BufferTooSmallError;
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), BufferTooSmallError> {
+        if bytes.len() > self.0.len() {
+            return Err(BufferTooSmallError);
+        }
+        let (head, tail) = core::mem::take(&mut self.0).split_at_mut(bytes.len());
+        head.copy_from_slice(bytes);
+        self.0 = tail;
+        Ok(())
+    }
+}
+
+/// Writes to an [`std::io::Write`].
+#[cfg(feature = "std")]
+pub struct IoSink<W>(pub W);
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Sink for IoSink<W> {
+    type Error = std::io::Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.0.write_all(bytes)
+    }
+}
+
+/// `\` followed by the 4 bytes of the address and the big-endian port.
+pub fn write_socket_addr_v4<S: Sink>(mut w: S, addr: &SocketAddrV4) -> Result<(), S::Error> {
+    w.write_all(b"\\")?;
+    w.write_all(&addr.ip().octets())?;
+    w.write_all(&addr.port().to_be_bytes())
+}
+
+/// `/` followed by the 16 bytes of the address and the big-endian port.
+pub fn write_socket_addr_v6<S: Sink>(mut w: S, addr: &SocketAddrV6) -> Result<(), S::Error> {
+    w.write_all(b"/")?;
+    w.write_all(&addr.ip().octets())?;
+    w.write_all(&addr.port().to_be_bytes())
+}
+
+pub fn write_socket_addr<S: Sink>(w: S, addr: &SocketAddr) -> Result<(), S::Error> {
+    match addr {
+        SocketAddr::V4(addr) => write_socket_addr_v4(w, addr),
+        SocketAddr::V6(addr) => write_socket_addr_v6(w, addr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameName, GetServers, GetServersFilter, ProtocolVersion};
+
+    /// Also run by `cargo test --no-default-features`, to check that the
+    /// encoders build and work without `std`.
+    #[test]
+    fn write_to_slice_without_std() {
+        let request = GetServers {
+            game_name: Some(GameName::try_from("Xonotic").unwrap()),
+            protocol_version: ProtocolVersion::try_from("3").unwrap(),
+            filter: GetServersFilter::all(),
+        };
+        let mut buf = [0; 64];
+        let len = request.write_to_slice(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"\xFF\xFF\xFF\xFFgetservers Xonotic 3 empty full"
+        );
+        assert_eq!(
+            request.write_to_slice(&mut buf[..len - 1]),
+            Err(BufferTooSmallError)
+        );
+    }
+
+    #[test]
+    fn slice_sink_advances_past_each_write() {
+        let mut buf = [0; 10];
+        let mut sink = SliceSink(&mut buf);
+        let addr = SocketAddrV4::new([1, 2, 3, 4].into(), 26000);
+        write_socket_addr(&mut sink, &addr.into()).unwrap();
+        sink.write_all(b"!").unwrap();
+        assert_eq!(sink.0.len(), 2);
+        assert_eq!(sink.write_all(b"abc"), Err(BufferTooSmallError));
+        assert_eq!(buf[..8], *b"\\\x01\x02\x03\x04\x65\x90!");
+    }
+}
@@ -0,0 +1,367 @@
+#[cfg(feature = "std")]
+use crate::{
+    encode::{IoSink, Sink},
+    CountingWriter,
+};
+use alloc::{
+    borrow::Cow,
+    string::String,
+    vec::{self, Vec},
+};
+use core::{fmt, ops::Index, str::Utf8Error};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Error)]
+#[error("infostring keys and values must not contain '\\' or '\\n'")]
+pub struct InvalidInfoStringError;
+
+fn is_info_string_byte(b: &u8) -> bool {
+    *b != b'\\' && *b != b'\n'
+}
+
+/// Key-value pairs of an infostring (`\key\value\key\value...`),
+/// kept in the order they were received.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InfoString {
+    key_values: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl InfoString {
+    pub fn new() -> InfoString {
+        InfoString::default()
+    }
+
+    /// Value of the first entry with `key`.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.key_values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_slice())
+    }
+
+    /// Like [`InfoString::get`], but ignoring ASCII case and the
+    /// whitespace around both keys, as some engines vary in both.
+    pub fn get_ci(&self, key: &[u8]) -> Option<&[u8]> {
+        let key = key.trim_ascii();
+        self.key_values
+            .iter()
+            .find(|(k, _)| k.trim_ascii().eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_slice())
+    }
+
+    /// Value of `key` parsed as an unsigned integer.
+    pub fn get_u32(&self, key: &[u8]) -> Option<u32> {
+        core::str::from_utf8(self.get(key)?).ok()?.parse().ok()
+    }
+
+    /// Value of `key` parsed as a boolean, `1` or `true` for true and `0`
+    /// or `false` for false, ignoring case.
+    pub fn get_bool(&self, key: &[u8]) -> Option<bool> {
+        let value = self.get(key)?;
+        if value == b"1" || value.eq_ignore_ascii_case(b"true") {
+            Some(true)
+        } else if value == b"0" || value.eq_ignore_ascii_case(b"false") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Value of the `hostname` key, or of the `sv_hostname` key sent in
+    /// every "statusResponse" and by most engines in "infoResponse".
+    pub fn hostname(&self) -> Option<&[u8]> {
+        self.get(b"hostname").or_else(|| self.get(b"sv_hostname"))
+    }
+
+    /// Value of the `gametype` key.
+    pub fn gametype(&self) -> Option<&[u8]> {
+        self.get(b"gametype")
+    }
+
+    /// Value of the `clients` key, the number of players on the server.
+    pub fn clients(&self) -> Option<u32> {
+        self.get_u32(b"clients")
+    }
+
+    /// Value of the `sv_maxclients` key.
+    pub fn max_clients(&self) -> Option<u32> {
+        self.get_u32(b"sv_maxclients")
+    }
+
+    /// Value of the `mapname` key.
+    pub fn map_name(&self) -> Option<&[u8]> {
+        self.get(b"mapname")
+    }
+
+    /// Value of the `protocol` key.
+    pub fn protocol(&self) -> Option<u32> {
+        self.get_u32(b"protocol")
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn push(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.key_values.push((key, value));
+    }
+
+    /// Iterate over the entries in their original order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.key_values
+            .iter()
+            .map(|(k, v)| (k.as_slice(), v.as_slice()))
+    }
+
+    /// Iterate over the entries decoded as UTF-8, replacing invalid
+    /// sequences with `U+FFFD`.
+    pub fn iter_str(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+        self.iter()
+            .map(|(k, v)| (String::from_utf8_lossy(k), String::from_utf8_lossy(v)))
+    }
+
+    /// Iterate over the entries decoded as UTF-8, yielding an error for
+    /// entries that aren't valid UTF-8.
+    pub fn try_iter_str(&self) -> impl Iterator<Item = Result<(&str, &str), Utf8Error>> {
+        self.iter()
+            .map(|(k, v)| Ok((core::str::from_utf8(k)?, core::str::from_utf8(v)?)))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.key_values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.key_values.is_empty()
+    }
+
+    pub fn into_inner(self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.key_values
+    }
+
+    /// Check that no key or value contains a `\` or `\n`, which would
+    /// corrupt the encoded infostring.
+    pub fn validate(&self) -> Result<(), InvalidInfoStringError> {
+        let valid = self
+            .iter()
+            .all(|(k, v)| k.iter().all(is_info_string_byte) && v.iter().all(is_info_string_byte));
+        if !valid {
+            return Err(InvalidInfoStringError);
+        }
+        Ok(())
+    }
+
+    /// Write the entries as `\key\value\key\value...`.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] wrapping an
+    /// [`InvalidInfoStringError`] before writing anything if any entry
+    /// doesn't pass [`InfoString::validate`].
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut writer = CountingWriter::new(IoSink(writer));
+        for (k, v) in self.iter() {
+            writer.write_all(b"\\")?;
+            writer.write_all(k)?;
+            writer.write_all(b"\\")?;
+            writer.write_all(v)?;
+        }
+        Ok(writer.written)
+    }
+}
+
+impl From<Vec<(Vec<u8>, Vec<u8>)>> for InfoString {
+    fn from(key_values: Vec<(Vec<u8>, Vec<u8>)>) -> InfoString {
+        InfoString { key_values }
+    }
+}
+
+impl<'a> From<Vec<(&'a [u8], &'a [u8])>> for InfoString {
+    fn from(key_values: Vec<(&'a [u8], &'a [u8])>) -> InfoString {
+        key_values
+            .into_iter()
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect()
+    }
+}
+
+impl FromIterator<(Vec<u8>, Vec<u8>)> for InfoString {
+    fn from_iter<T: IntoIterator<Item = (Vec<u8>, Vec<u8>)>>(iter: T) -> InfoString {
+        InfoString {
+            key_values: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for InfoString {
+    type Item = (Vec<u8>, Vec<u8>);
+    type IntoIter = vec::IntoIter<(Vec<u8>, Vec<u8>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.key_values.into_iter()
+    }
+}
+
+/// One `key=value` line per entry, lossily decoded as UTF-8.
+impl fmt::Display for InfoString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (k, v) in self.iter_str() {
+            writeln!(f, "{k}={v}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Index<&[u8]> for InfoString {
+    type Output = [u8];
+
+    /// # Panics
+    ///
+    /// Panics if `key` is not present.
+    fn index(&self, key: &[u8]) -> &[u8] {
+        self.get(key).expect("key not found in infostring")
+    }
+}
+
+/// Keys and values are (de)serialized as a map of UTF-8 strings, lossily
+/// replacing invalid UTF-8 sequences.
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::InfoString;
+    use alloc::string::String;
+    use core::fmt;
+    use serde::{
+        de::{MapAccess, Visitor},
+        ser::SerializeMap,
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    impl Serialize for InfoString {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(self.len()))?;
+            for (k, v) in self.iter() {
+                map.serialize_entry(&String::from_utf8_lossy(k), &String::from_utf8_lossy(v))?;
+            }
+            map.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for InfoString {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct InfoStringVisitor;
+
+            impl<'de> Visitor<'de> for InfoStringVisitor {
+                type Value = InfoString;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a map of strings")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(
+                    self,
+                    mut access: A,
+                ) -> Result<InfoString, A::Error> {
+                    let mut info_string = InfoString::new();
+                    while let Some((k, v)) = access.next_entry::<String, String>()? {
+                        info_string.push(k.into_bytes(), v.into_bytes());
+                    }
+                    Ok(info_string)
+                }
+            }
+
+            deserializer.deserialize_map(InfoStringVisitor)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_ci_ignores_case_and_surrounding_whitespace() {
+        let mut info_string = InfoString::new();
+        info_string.push(b" Sv_Hostname ".to_vec(), b"server".to_vec());
+        assert_eq!(info_string.get(b"sv_hostname"), None);
+        assert_eq!(info_string.get_ci(b"sv_hostname"), Some(&b"server"[..]));
+        assert_eq!(info_string.get_ci(b" SV_HOSTNAME "), Some(&b"server"[..]));
+        assert_eq!(info_string.get_ci(b"hostname"), None);
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let info_string: InfoString = vec![
+            (&b"sv_hostname"[..], &b"server"[..]),
+            (b"gametype", b"ctf"),
+            (b"clients", b"3"),
+            (b"sv_maxclients", b"16"),
+            (b"mapname", b"q3dm17"),
+            (b"protocol", b"68"),
+        ]
+        .into();
+        assert_eq!(info_string.hostname(), Some(&b"server"[..]));
+        assert_eq!(info_string.gametype(), Some(&b"ctf"[..]));
+        assert_eq!(info_string.clients(), Some(3));
+        assert_eq!(info_string.max_clients(), Some(16));
+        assert_eq!(info_string.map_name(), Some(&b"q3dm17"[..]));
+        assert_eq!(info_string.protocol(), Some(68));
+    }
+
+    #[test]
+    fn typed_accessors_prefer_hostname() {
+        let info_string: InfoString =
+            vec![(&b"sv_hostname"[..], &b"b"[..]), (b"hostname", b"a")].into();
+        assert_eq!(info_string.hostname(), Some(&b"a"[..]));
+    }
+
+    #[test]
+    fn typed_accessors_absent() {
+        let info_string = InfoString::new();
+        assert_eq!(info_string.hostname(), None);
+        assert_eq!(info_string.gametype(), None);
+        assert_eq!(info_string.clients(), None);
+        assert_eq!(info_string.max_clients(), None);
+        assert_eq!(info_string.map_name(), None);
+        assert_eq!(info_string.protocol(), None);
+    }
+
+    #[test]
+    fn typed_accessors_non_numeric() {
+        let info_string: InfoString = vec![
+            (&b"clients"[..], &b"three"[..]),
+            (b"sv_maxclients", b"-1"),
+            (b"protocol", b""),
+        ]
+        .into();
+        assert_eq!(info_string.clients(), None);
+        assert_eq!(info_string.max_clients(), None);
+        assert_eq!(info_string.protocol(), None);
+    }
+
+    #[test]
+    fn write_all_to_rejects_backslashes_and_new_lines() {
+        let info_string: InfoString = vec![(&b"sv_hostname"[..], &b"a\\b"[..])].into();
+        assert_eq!(info_string.validate(), Err(InvalidInfoStringError));
+        let mut bytes = vec![];
+        let error = info_string.write_all_to(&mut bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(bytes.is_empty());
+
+        let info_string: InfoString = vec![(&b"key\\"[..], &b"value"[..])].into();
+        assert!(info_string.write_all_to(vec![]).is_err());
+        let info_string: InfoString = vec![(&b"key"[..], &b"value\n"[..])].into();
+        assert!(info_string.write_all_to(vec![]).is_err());
+
+        let info_string: InfoString = vec![(&b"a"[..], &b"b c"[..]), (b"d", b"")].into();
+        let mut bytes = vec![];
+        assert_eq!(info_string.write_all_to(&mut bytes).unwrap(), 9);
+        assert_eq!(bytes, b"\\a\\b c\\d\\");
+    }
+}
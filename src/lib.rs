@@ -1,18 +1,99 @@
 //! Original protocol technical information [here](https://github.com/kphillisjr/dpmaster/blob/master/doc/techinfo.txt).
+//!
+//! Without the default `std` feature the crate is `no_std` with `alloc`:
+//! the messages can be built and encoded with `write_to_slice`, while
+//! parsing, `write_all_to` and the clients need `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use nom::Parser;
-use nom_supreme::final_parser::final_parser;
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec, vec::Vec};
+use core::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+};
+#[cfg(feature = "std")]
+use encode::IoSink;
+use encode::{Sink, SliceSink};
+#[cfg(feature = "std")]
+use nom::{combinator::rest, Offset, Parser};
+#[cfg(feature = "std")]
+use nom_supreme::{
+    error::{BaseErrorKind, ErrorTree, Expectation},
+    final_parser::final_parser,
+};
+#[cfg(feature = "std")]
 use std::{
-    borrow::Cow,
-    collections::HashMap,
+    collections::HashSet,
     io::{self, Write},
-    net::{SocketAddr, SocketAddrV4},
 };
 use thiserror::Error;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod color;
+mod encode;
+mod info_string;
+#[cfg(feature = "std")]
 mod parse;
 
+pub use info_string::{InfoString, InvalidInfoStringError};
+
 const OOB: &[u8] = b"\xFF\xFF\xFF\xFF";
+const EOT: &[u8] = b"\\EOT\0\0\0";
+
+/// Out-of-band prefix of the connectionless packets of an engine.
+///
+/// Messages are always written and parsed with the standard four `0xFF`
+/// bytes, a `Protocol` translates them at the socket boundary for forks
+/// using another prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Protocol<'a> {
+    pub prefix: &'a [u8],
+}
+
+impl Protocol<'_> {
+    /// The four `0xFF` bytes prefix.
+    pub const STANDARD: Protocol<'static> = Protocol { prefix: OOB };
+
+    /// Write `message`, as written by a `write_all_to`, with its standard
+    /// prefix replaced by `self.prefix`.
+    #[cfg(feature = "std")]
+    pub fn encode<W: Write>(&self, message: &[u8], writer: W) -> io::Result<usize> {
+        let body = message.strip_prefix(OOB).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "message doesn't start with the out-of-band prefix",
+            )
+        })?;
+        let mut writer = CountingWriter::new(IoSink(writer));
+        writer.write_all(self.prefix)?;
+        writer.write_all(body)?;
+        Ok(writer.written)
+    }
+
+    /// Replace `self.prefix` at the start of a received datagram with the
+    /// standard prefix, so it can be given to the `parse` functions.
+    /// Returns `None` if the datagram doesn't start with `self.prefix`.
+    pub fn decode<'b>(&self, datagram: &'b [u8]) -> Option<Cow<'b, [u8]>> {
+        let body = datagram.strip_prefix(self.prefix)?;
+        if self.prefix == OOB {
+            return Some(Cow::Borrowed(datagram));
+        }
+        let mut bytes = Vec::with_capacity(OOB.len() + body.len());
+        bytes.extend_from_slice(OOB);
+        bytes.extend_from_slice(body);
+        Some(Cow::Owned(bytes))
+    }
+}
+
+impl Default for Protocol<'_> {
+    fn default() -> Self {
+        Protocol::STANDARD
+    }
+}
 
 macro_rules! define_checked_string {
     (
@@ -45,6 +126,48 @@ macro_rules! define_checked_string {
             pub fn get(&self) -> &Cow<'_, [u8]> {
                 &self.0
             }
+
+            /// Copy the bytes if borrowed, to keep the value around.
+            pub fn into_owned(self) -> $struct_name<'static> {
+                $struct_name(Cow::Owned(self.0.into_owned()))
+            }
+
+            /// Whether `b` is allowed.
+            #[inline]
+            pub fn is_valid_byte(b: u8) -> bool {
+                let check: fn(&u8) -> bool = $check;
+                check(&b)
+            }
+
+            /// Build a valid value out of `input` by dropping the bytes
+            /// that aren't allowed.
+            pub fn sanitized($arg_name: &[u8]) -> $struct_name<'static> {
+                $struct_name(Cow::Owned(
+                    $arg_name
+                        .iter()
+                        .copied()
+                        .filter(|b| $struct_name::is_valid_byte(*b))
+                        .collect(),
+                ))
+            }
+
+            #[inline]
+            pub fn len(&self) -> usize {
+                self.0.len()
+            }
+
+            #[inline]
+            pub fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+        }
+        impl core::ops::Deref for $struct_name<'_> {
+            type Target = [u8];
+
+            #[inline]
+            fn deref(&self) -> &[u8] {
+                &self.0
+            }
         }
         impl AsRef<[u8]> for $struct_name<'_> {
             #[inline]
@@ -87,15 +210,61 @@ macro_rules! define_checked_string {
     };
 }
 
+fn is_challenge_byte(b: &u8) -> bool {
+    match *b {
+        b'\\' | b'/' | b';' | b'"' | b'%' => false,
+        33..=126 => true,
+        _ => false,
+    }
+}
+
 define_checked_string! {
     "A challenge must only contains ASCII characters but exclude '\\', '/', ';', '\"' and '%'",
     NewChallengeError,
     Challenge,
     challenge,
-    |b| match *b {
-        b'\\' | b'/' | b';' | b'"' | b'%' => false,
-        33..=126 => true,
-        _ => false,
+    is_challenge_byte
+}
+
+/// Error of the `new_nonempty` constructors.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NewNonEmptyError<E> {
+    #[error("Must not be empty")]
+    Empty,
+    #[error(transparent)]
+    Invalid(E),
+}
+
+impl Challenge<'_> {
+    /// Like [`Challenge::new`], but also reject an empty challenge, which
+    /// servers don't answer to.
+    pub fn new_nonempty(
+        challenge: Cow<'_, [u8]>,
+    ) -> Result<Challenge<'_>, NewNonEmptyError<NewChallengeError>> {
+        if challenge.is_empty() {
+            return Err(NewNonEmptyError::Empty);
+        }
+        Challenge::new(challenge).map_err(NewNonEmptyError::Invalid)
+    }
+}
+
+#[cfg(feature = "rand")]
+impl Challenge<'static> {
+    /// Generate a random challenge of `len` bytes, using only the bytes
+    /// allowed in a challenge.
+    pub fn generate(len: usize) -> Challenge<'static> {
+        use rand::seq::IndexedRandom;
+
+        let allowed: Vec<u8> = (0..=u8::MAX).filter(is_challenge_byte).collect();
+        let mut rng = rand::rng();
+        let challenge = (0..len)
+            .map(|_| {
+                *allowed
+                    .choose(&mut rng)
+                    .expect("allowed bytes is not empty")
+            })
+            .collect();
+        Challenge::new_unchecked(Cow::Owned(challenge))
     }
 }
 define_checked_string! {
@@ -112,6 +281,38 @@ define_checked_string! {
     game_name,
     |b| !b.is_ascii_whitespace()
 }
+impl GameName<'_> {
+    /// Like [`GameName::new`], but also reject an empty game name.
+    pub fn new_nonempty(
+        game_name: Cow<'_, [u8]>,
+    ) -> Result<GameName<'_>, NewNonEmptyError<NewGameNameError>> {
+        if game_name.is_empty() {
+            return Err(NewNonEmptyError::Empty);
+        }
+        GameName::new(game_name).map_err(NewNonEmptyError::Invalid)
+    }
+}
+define_checked_string! {
+    "Game types must not contain any whitespace",
+    NewGameTypeError,
+    GameType,
+    game_type,
+    |b| !b.is_ascii_whitespace()
+}
+define_checked_string! {
+    "Commands must not contain any whitespace",
+    NewCommandError,
+    Command,
+    command,
+    |b| !b.is_ascii_whitespace()
+}
+define_checked_string! {
+    "Rcon passwords must not contain any whitespace",
+    NewRconPasswordError,
+    RconPassword,
+    rcon_password,
+    |b| !b.is_ascii_whitespace()
+}
 define_checked_string! {
     "Protocol version must only be a number",
     NewProtocolVersionError,
@@ -120,7 +321,255 @@ define_checked_string! {
     |b| b.is_ascii_digit()
 }
 
-type ParseResult<'a, T> = std::result::Result<T, nom_supreme::error::ErrorTree<&'a [u8]>>;
+/// Error of [`ProtocolVersion::new_u32`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NewU32ProtocolVersionError {
+    #[error(transparent)]
+    Invalid(#[from] NewProtocolVersionError),
+    #[error("Protocol version must be a number that fits in a u32")]
+    OutOfRange,
+}
+
+impl ProtocolVersion<'_> {
+    /// Like [`ProtocolVersion::new`], but also reject an empty version or
+    /// one that overflows a `u32`, which masters may reject.
+    pub fn new_u32(
+        protocol_version: Cow<'_, [u8]>,
+    ) -> Result<ProtocolVersion<'_>, NewU32ProtocolVersionError> {
+        let protocol_version = ProtocolVersion::new(protocol_version)?;
+        if protocol_version.as_u32().is_none() {
+            return Err(NewU32ProtocolVersionError::OutOfRange);
+        }
+        Ok(protocol_version)
+    }
+
+    /// The version as a number, leading zeros ignored. `None` if empty,
+    /// not only digits or overflowing a `u32`.
+    pub fn as_u32(&self) -> Option<u32> {
+        if self.0.is_empty() {
+            return None;
+        }
+        self.0.iter().try_fold(0u32, |version, &b| {
+            if !b.is_ascii_digit() {
+                return None;
+            }
+            version.checked_mul(10)?.checked_add(u32::from(b - b'0'))
+        })
+    }
+}
+
+/// Forward the infostring accessors of [`InfoString`] to the `key_values`
+/// of a response.
+macro_rules! impl_info_string_accessors {
+    ($struct_name:ident) => {
+        impl $struct_name {
+            #[inline]
+            pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+                self.key_values.get(key)
+            }
+
+            #[inline]
+            pub fn get_ci(&self, key: &[u8]) -> Option<&[u8]> {
+                self.key_values.get_ci(key)
+            }
+
+            #[inline]
+            pub fn iter_str(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+                self.key_values.iter_str()
+            }
+
+            #[inline]
+            pub fn try_iter_str(
+                &self,
+            ) -> impl Iterator<Item = Result<(&str, &str), core::str::Utf8Error>> {
+                self.key_values.try_iter_str()
+            }
+
+            #[inline]
+            pub fn get_u32(&self, key: &[u8]) -> Option<u32> {
+                self.key_values.get_u32(key)
+            }
+
+            #[inline]
+            pub fn get_bool(&self, key: &[u8]) -> Option<bool> {
+                self.key_values.get_bool(key)
+            }
+
+            #[inline]
+            pub fn hostname(&self) -> Option<&[u8]> {
+                self.key_values.hostname()
+            }
+
+            #[inline]
+            pub fn gametype(&self) -> Option<&[u8]> {
+                self.key_values.gametype()
+            }
+
+            #[inline]
+            pub fn clients(&self) -> Option<u32> {
+                self.key_values.clients()
+            }
+
+            #[inline]
+            pub fn max_clients(&self) -> Option<u32> {
+                self.key_values.max_clients()
+            }
+
+            #[inline]
+            pub fn map_name(&self) -> Option<&[u8]> {
+                self.key_values.map_name()
+            }
+
+            #[inline]
+            pub fn protocol(&self) -> Option<u32> {
+                self.key_values.protocol()
+            }
+        }
+    };
+}
+
+/// Writer counting the bytes written through it, so every `write_all_to`
+/// can report how many bytes the message takes.
+struct CountingWriter<W> {
+    inner: W,
+    written: usize,
+}
+
+impl<S: Sink> CountingWriter<S> {
+    fn new(inner: S) -> CountingWriter<S> {
+        CountingWriter { inner, written: 0 }
+    }
+}
+
+impl<S: Sink> Sink for CountingWriter<S> {
+    type Error = S::Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), S::Error> {
+        self.inner.write_all(bytes)?;
+        self.written += bytes.len();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Error)]
+#[error("The buffer is too small to fit the message")]
+pub struct BufferTooSmallError;
+
+/// Add `write_to_slice`, and `write_to_buf` with the `bytes` feature,
+/// to write the message without an [`io::Write`] at hand.
+macro_rules! impl_write_to_slice {
+    ($($message:ty),* $(,)?) => {
+        $(
+            impl $message {
+                /// Write the message at the start of `buf`, returning the
+                /// number of bytes written. The content of `buf` is
+                /// unspecified when it is too small.
+                pub fn write_to_slice(&self, buf: &mut [u8]) -> Result<usize, BufferTooSmallError> {
+                    self.write_to_sink(SliceSink(buf))
+                }
+
+                /// Write the message at the end of `buf`, such as a
+                /// `BytesMut` of a codec, returning the number of bytes
+                /// written. Fails when `buf` runs out of space.
+                #[cfg(feature = "bytes")]
+                pub fn write_to_buf<B: bytes::BufMut>(&self, buf: &mut B) -> io::Result<usize> {
+                    self.write_all_to(bytes::BufMut::writer(buf))
+                }
+            }
+        )*
+    };
+}
+
+impl_write_to_slice!(
+    GetInfo<'_>,
+    GetStatus<'_>,
+    GetChallenge,
+    HeartBeat<'_>,
+    GetServers<'_>,
+    GetServersResponse,
+    GetServersExt<'_>,
+    GetServersExtResponse,
+    GetMotd<'_>,
+    Rcon<'_>,
+);
+
+/// Why a received message couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseResponseError {
+    /// Carries the description of where parsing failed.
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+    /// An out of band message, but not the one expected.
+    #[error("Unexpected message")]
+    UnexpectedMessage,
+    /// The message was followed by this many bytes.
+    #[error("{0} bytes of trailing data after the message")]
+    TrailingData(usize),
+}
+
+#[cfg(feature = "std")]
+impl ParseResponseError {
+    /// Classify the error returned by parsing `bytes`.
+    pub fn from_error_tree(bytes: &[u8], error: ErrorTree<&[u8]>) -> ParseResponseError {
+        /// Locations of the errors at the bottom of the tree, and whether
+        /// the end of input was expected there.
+        fn bases<'a>(error: &ErrorTree<&'a [u8]>, out: &mut Vec<(&'a [u8], bool)>) {
+            match error {
+                ErrorTree::Base { location, kind } => out.push((
+                    location,
+                    matches!(kind, BaseErrorKind::Expected(Expectation::Eof)),
+                )),
+                ErrorTree::Stack { base, .. } => bases(base, out),
+                ErrorTree::Alt(siblings) => siblings.iter().for_each(|e| bases(e, out)),
+            }
+        }
+
+        let mut base_errors = vec![];
+        bases(&error, &mut base_errors);
+        if let [(location, true)] = base_errors[..] {
+            return ParseResponseError::TrailingData(location.len());
+        }
+        let after_oob = bytes.starts_with(OOB)
+            && base_errors
+                .iter()
+                .all(|(location, _)| bytes.offset(location) == OOB.len());
+        if after_oob {
+            return ParseResponseError::UnexpectedMessage;
+        }
+        let error = error.map_locations(|location| bytes.offset(location));
+        ParseResponseError::InvalidResponse(error.to_string())
+    }
+}
+
+#[cfg(feature = "std")]
+type ParseResult<'a, T> = core::result::Result<T, ErrorTree<&'a [u8]>>;
+
+/// Bounds on what a parsed message may contain, to reject messages from
+/// untrusted servers early. See the `parse_with_limits` functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Most player infos in a "statusResponse".
+    pub max_players: usize,
+    /// Longest infostring key, in bytes.
+    pub max_key_len: usize,
+    /// Longest infostring value, in bytes.
+    pub max_value_len: usize,
+}
+
+impl ParseLimits {
+    /// No bounds, as used by the `parse` functions.
+    pub const NONE: ParseLimits = ParseLimits {
+        max_players: usize::MAX,
+        max_key_len: usize::MAX,
+        max_value_len: usize::MAX,
+    };
+}
+
+impl Default for ParseLimits {
+    fn default() -> ParseLimits {
+        ParseLimits::NONE
+    }
+}
 
 /// This message is sent by a master to a server, usually in response
 /// to an "hearbeat" by this very server. It is used by the master to
@@ -128,90 +577,425 @@ type ParseResult<'a, T> = std::result::Result<T, nom_supreme::error::ErrorTree<&
 /// challenge string is necessary to authenticate the server's
 /// corresponding "infoResponse".
 #[doc(alias = "getinfo")]
+#[derive(Debug)]
 pub struct GetInfo<'a> {
     pub challenge: Challenge<'a>,
 }
 
 impl GetInfo<'_> {
-    pub fn new(challenge: Challenge) -> GetInfo<'_> {
+    pub fn new(challenge: Challenge<'_>) -> GetInfo<'_> {
         GetInfo { challenge }
     }
-    pub fn write_all_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetInfo<'_>> {
+        final_parser(parse::getinfo.map(|challenge| GetInfo { challenge }))(bytes)
+    }
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    /// Like [`GetInfo::write_all_to`], but with another spelling of the
+    /// `getinfo` command, for engine forks expecting one.
+    #[cfg(feature = "std")]
+    pub fn write_all_with_command_to<W: Write>(
+        &self,
+        command: &Command<'_>,
+        writer: W,
+    ) -> io::Result<usize> {
+        self.write_with_command_to_sink(command, IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        self.write_with_command_to_sink(&Command::new_unchecked(Cow::Borrowed(b"getinfo")), writer)
+    }
+
+    fn write_with_command_to_sink<S: Sink>(
+        &self,
+        command: &Command<'_>,
+        writer: S,
+    ) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
         writer.write_all(OOB)?;
-        writer.write_all(b"getinfo")?;
+        writer.write_all(command)?;
         writer.write_all(b" ")?;
         writer.write_all(self.challenge.as_ref())?;
-        Ok(())
+        Ok(writer.written)
     }
 }
 
 /// An "infoResponse" message is the reponse to a "getinfo" request.
 /// It contains an infostring including the most important information
 /// about the current server state.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[doc(alias = "infoResponse")]
 pub struct InfoResponse {
-    pub key_values: HashMap<Vec<u8>, Vec<u8>>,
+    pub key_values: InfoString,
 }
 
 impl InfoResponse {
-    pub fn parse(bytes: &[u8]) -> ParseResult<InfoResponse> {
-        final_parser(parse::infoResponse.map(|key_values| {
-            InfoResponse {
-                key_values: key_values
-                    .into_iter()
-                    .map(|(k, v)| (k.to_vec(), v.to_vec()))
-                    .collect(),
-            }
-        }))(bytes)
+    /// Fails on any data after the message, which
+    /// [`ParseResponseError::from_error_tree`] reports as
+    /// [`ParseResponseError::TrailingData`].
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, InfoResponse> {
+        InfoResponse::parse_with_limits(bytes, ParseLimits::NONE)
+    }
+
+    /// Like [`InfoResponse::parse`], but also accept data after the
+    /// message and return it, e.g. to inspect fields a server appends.
+    #[cfg(feature = "std")]
+    pub fn parse_partial(bytes: &[u8]) -> ParseResult<'_, (InfoResponse, &[u8])> {
+        final_parser(
+            parse::infoResponse_limited(ParseLimits::NONE)
+                .map(|key_values| InfoResponse {
+                    key_values: key_values.into(),
+                })
+                .and(rest),
+        )(bytes)
+    }
+
+    /// Like [`InfoResponse::parse`], but fail when the response exceeds
+    /// `limits`.
+    #[cfg(feature = "std")]
+    pub fn parse_with_limits(bytes: &[u8], limits: ParseLimits) -> ParseResult<'_, InfoResponse> {
+        final_parser(
+            parse::infoResponse_limited(limits).map(|key_values| InfoResponse {
+                key_values: key_values.into(),
+            }),
+        )(bytes)
+    }
+}
+
+impl_info_string_accessors!(InfoResponse);
+
+impl fmt::Display for InfoResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.key_values, f)
     }
 }
 
 #[doc(alias = "getstatus")]
+#[derive(Debug)]
 pub struct GetStatus<'a> {
     pub challenge: Challenge<'a>,
 }
 impl GetStatus<'_> {
-    pub fn new(challenge: Challenge) -> GetStatus<'_> {
+    pub fn new(challenge: Challenge<'_>) -> GetStatus<'_> {
         GetStatus { challenge }
     }
 
-    pub fn write_all_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetStatus<'_>> {
+        final_parser(parse::getstatus.map(|challenge| GetStatus { challenge }))(bytes)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    /// Like [`GetStatus::write_all_to`], but with another spelling of the
+    /// `getstatus` command, for engine forks expecting one.
+    #[cfg(feature = "std")]
+    pub fn write_all_with_command_to<W: Write>(
+        &self,
+        command: &Command<'_>,
+        writer: W,
+    ) -> io::Result<usize> {
+        self.write_with_command_to_sink(command, IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        self.write_with_command_to_sink(
+            &Command::new_unchecked(Cow::Borrowed(b"getstatus")),
+            writer,
+        )
+    }
+
+    fn write_with_command_to_sink<S: Sink>(
+        &self,
+        command: &Command<'_>,
+        writer: S,
+    ) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
         writer.write_all(OOB)?;
-        writer.write_all(b"getstatus")?;
+        writer.write_all(command)?;
         writer.write_all(b" ")?;
-        writer.write_all(self.challenge.get())?;
-        Ok(())
+        writer.write_all(self.challenge.as_ref())?;
+        Ok(writer.written)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[doc(alias = "statusResponse")]
 pub struct StatusResponse {
-    pub key_values: HashMap<Vec<u8>, Vec<u8>>,
+    pub key_values: InfoString,
     pub player_infos: Vec<PlayerInfo>,
 }
-pub use parse::PlayerInfo;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerInfo {
+    pub frags: i32,
+    pub ping: i32,
+    /// Raw bytes of the name, which isn't necessarily UTF-8 (e.g. Latin-1).
+    pub name: Vec<u8>,
+    /// Only sent by some engines (e.g. DarkPlaces), Quake III Arena omits it.
+    pub team: Option<i32>,
+    /// Extra fields some engines append to the player line, unparsed and
+    /// without the separating space.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rest: Vec<u8>,
+}
+
+/// [`PlayerInfo`] borrowing its name from the parsed bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlayerInfoRef<'a> {
+    pub frags: i32,
+    pub ping: i32,
+    pub name: &'a [u8],
+    pub team: Option<i32>,
+    pub rest: &'a [u8],
+}
+
+impl PlayerInfoRef<'_> {
+    pub fn to_owned(&self) -> PlayerInfo {
+        PlayerInfo {
+            frags: self.frags,
+            ping: self.ping,
+            name: self.name.to_vec(),
+            team: self.team,
+            rest: self.rest.to_vec(),
+        }
+    }
+}
+
+impl PlayerInfo {
+    /// The name decoded as UTF-8, replacing invalid sequences with `U+FFFD`.
+    pub fn name_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.name)
+    }
+}
+
+impl fmt::Display for PlayerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" frags={} ping={}",
+            self.name_lossy(),
+            self.frags,
+            self.ping
+        )?;
+        if let Some(team) = self.team {
+            write!(f, " team={team}")?;
+        }
+        Ok(())
+    }
+}
+
+impl_info_string_accessors!(StatusResponse);
+
+/// The infostring followed by one line per player.
+impl fmt::Display for StatusResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.key_values, f)?;
+        for player in &self.player_infos {
+            writeln!(f, "{player}")?;
+        }
+        Ok(())
+    }
+}
 
 impl StatusResponse {
-    pub fn parse(bytes: &[u8]) -> ParseResult<StatusResponse> {
-        final_parser(parse::statusResponse.map(|(kv, player_infos)| {
-            StatusResponse {
-                key_values: kv
-                    .into_iter()
-                    .map(|(k, v)| (k.to_vec(), v.to_vec()))
-                    .collect(),
+    /// Fails on any data after the message, which
+    /// [`ParseResponseError::from_error_tree`] reports as
+    /// [`ParseResponseError::TrailingData`].
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, StatusResponse> {
+        StatusResponse::parse_with_limits(bytes, ParseLimits::NONE)
+    }
+
+    /// Like [`StatusResponse::parse`], but also accept data after the
+    /// message and return it, e.g. to inspect lines a server appends.
+    #[cfg(feature = "std")]
+    pub fn parse_partial(bytes: &[u8]) -> ParseResult<'_, (StatusResponse, &[u8])> {
+        final_parser(
+            parse::statusResponse
+                .map(|(kv, player_infos)| StatusResponse {
+                    key_values: kv.into(),
+                    player_infos: player_infos.iter().map(PlayerInfoRef::to_owned).collect(),
+                })
+                .and(rest),
+        )(bytes)
+    }
+
+    /// Like [`StatusResponse::parse`], but fail when the response exceeds
+    /// `limits`.
+    #[cfg(feature = "std")]
+    pub fn parse_with_limits(bytes: &[u8], limits: ParseLimits) -> ParseResult<'_, StatusResponse> {
+        final_parser(
+            parse::statusResponse_limited(limits).map(|(kv, player_infos)| StatusResponse {
+                key_values: kv.into(),
+                player_infos: player_infos.iter().map(PlayerInfoRef::to_owned).collect(),
+            }),
+        )(bytes)
+    }
+
+    /// Minimum, maximum and average ping of the players, `None` without
+    /// players.
+    pub fn ping_stats(&self) -> Option<PingStats> {
+        let pings = self.player_infos.iter().map(|player| player.ping);
+        let min = pings.clone().min()?;
+        let max = pings.clone().max()?;
+        let avg = pings.map(f64::from).sum::<f64>() / self.player_infos.len() as f64;
+        Some(PingStats { min, max, avg })
+    }
+
+    /// The players, also iterated over by a `for` loop on a
+    /// `&StatusResponse`.
+    ///
+    /// ```
+    /// use dpmaster::{InfoString, PlayerInfo, StatusResponse};
+    ///
+    /// let player = |name: &str, frags| PlayerInfo {
+    ///     frags,
+    ///     ping: 50,
+    ///     name: name.as_bytes().to_vec(),
+    ///     team: None,
+    ///     rest: vec![],
+    /// };
+    /// let status = StatusResponse {
+    ///     key_values: InfoString::new(),
+    ///     player_infos: vec![player("alice", 3), player("bob", 1)],
+    /// };
+    /// let names: Vec<_> = status.players().map(|player| player.name_lossy()).collect();
+    /// assert_eq!(names, ["alice", "bob"]);
+    ///
+    /// let mut frags = 0;
+    /// for player in &status {
+    ///     frags += player.frags;
+    /// }
+    /// assert_eq!(frags, 4);
+    /// ```
+    pub fn players(&self) -> core::slice::Iter<'_, PlayerInfo> {
+        self.player_infos.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a StatusResponse {
+    type Item = &'a PlayerInfo;
+    type IntoIter = core::slice::Iter<'a, PlayerInfo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.players()
+    }
+}
+
+/// Ping statistics of the players of a [`StatusResponse`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PingStats {
+    pub min: i32,
+    pub max: i32,
+    pub avg: f64,
+}
+
+/// [`StatusResponse`] borrowing its keys, values and player names from the
+/// parsed bytes, to avoid copying them when they're only read once.
+#[derive(Debug)]
+pub struct StatusResponseRef<'a> {
+    pub key_values: Vec<(&'a [u8], &'a [u8])>,
+    pub player_infos: Vec<PlayerInfoRef<'a>>,
+}
+
+impl<'a> StatusResponseRef<'a> {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &'a [u8]) -> ParseResult<'a, StatusResponseRef<'a>> {
+        final_parser(
+            parse::statusResponse.map(|(key_values, player_infos)| StatusResponseRef {
+                key_values,
                 player_infos,
-            }
+            }),
+        )(bytes)
+    }
+
+    /// Value of the first entry with `key`.
+    pub fn get(&self, key: &[u8]) -> Option<&'a [u8]> {
+        self.key_values
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    pub fn to_owned(&self) -> StatusResponse {
+        StatusResponse {
+            key_values: self.key_values.clone().into(),
+            player_infos: self
+                .player_infos
+                .iter()
+                .map(PlayerInfoRef::to_owned)
+                .collect(),
+        }
+    }
+}
+
+/// A "getchallenge" message is sent to a server by a client before
+/// connecting. It triggers a "challengeResponse" from the server.
+#[doc(alias = "getchallenge")]
+#[derive(Debug, Default)]
+pub struct GetChallenge;
+
+impl GetChallenge {
+    /// Arguments after the command, which Quake3 clients send, are
+    /// accepted and ignored.
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetChallenge> {
+        final_parser(parse::getchallenge.map(|_| GetChallenge))(bytes)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(OOB)?;
+        writer.write_all(b"getchallenge")?;
+        Ok(writer.written)
+    }
+}
+
+/// A "challengeResponse" message is the response to a "getchallenge"
+/// request. Any data the server appends after the challenge is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[doc(alias = "challengeResponse")]
+pub struct ChallengeResponse {
+    pub challenge: Vec<u8>,
+}
+
+impl ChallengeResponse {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, ChallengeResponse> {
+        final_parser(parse::challengeResponse.map(|challenge| ChallengeResponse {
+            challenge: challenge.to_vec(),
         }))(bytes)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DatagramInfo {
+    /// A datagram without an EOT, more may follow.
     Single,
+    /// A datagram ending with an EOT, the last one of the response.
     /// (EOT stands for "End Of Transmission")
     Eot,
+    /// Collected from one or more datagrams without receiving an EOT.
     Collected,
 }
 
@@ -227,50 +1011,264 @@ pub struct HeartBeat<'a> {
 }
 
 impl HeartBeat<'_> {
-    pub fn write_all_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, HeartBeat<'_>> {
+        final_parser(parse::heartbeat.map(|protocol_string| HeartBeat { protocol_string }))(bytes)
+    }
+
+    /// Whether this heartbeat announces that the server is shutting down.
+    ///
+    /// Registration heartbeats carry the game's protocol string, such as
+    /// `DarkPlaces` or `QuakeArena-1`. Quake3-derived servers send a
+    /// string containing `flatline` when they go offline, such as
+    /// `flatline-1`, `WolfFlatline-1` or `ETFlatline-1`, so the master can
+    /// drop them from its list right away. The match ignores case.
+    pub fn is_flatline(&self) -> bool {
+        self.protocol_string
+            .get()
+            .windows(b"flatline".len())
+            .any(|w| w.eq_ignore_ascii_case(b"flatline"))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
         writer.write_all(OOB)?;
         writer.write_all(b"heartbeat")?;
         writer.write_all(b" ")?;
         writer.write_all(self.protocol_string.get())?;
         writer.write_all(b"\n")?;
-        Ok(())
+        Ok(writer.written)
     }
 }
 
-#[derive(Debug, Default)]
-pub struct GetServersFilter<'a> {
+/// The gametype a master should filter servers on.
+#[derive(Debug)]
+pub enum GameTypeFilter<'a> {
+    /// A gametype number, as used by Quake3 derived games, written as
+    /// `gametype=<number>`.
+    Numeric(u32),
+    /// A gametype keyword such as `ctf`, written as is.
+    Named(GameType<'a>),
+}
+
+impl<'a> GameTypeFilter<'a> {
+    /// `gametype=<number>` as a number, any other filter token as a name.
+    #[cfg(feature = "std")]
+    fn from_token(token: &'a [u8]) -> GameTypeFilter<'a> {
+        let number = token
+            .strip_prefix(b"gametype=")
+            .and_then(|number| core::str::from_utf8(number).ok()?.parse().ok());
+        match number {
+            Some(number) => GameTypeFilter::Numeric(number),
+            None => GameTypeFilter::Named(GameType::new_unchecked(Cow::Borrowed(token))),
+        }
+    }
+
+    /// Copy the bytes if borrowed, to keep the value around.
+    pub fn into_owned(self) -> GameTypeFilter<'static> {
+        match self {
+            GameTypeFilter::Numeric(number) => GameTypeFilter::Numeric(number),
+            GameTypeFilter::Named(name) => GameTypeFilter::Named(name.into_owned()),
+        }
+    }
+
+    fn write_to_sink<S: Sink>(&self, mut w: S) -> Result<(), S::Error> {
+        match self {
+            GameTypeFilter::Numeric(number) => {
+                w.write_all(format!(" gametype={number}").as_bytes())
+            }
+            GameTypeFilter::Named(name) => {
+                w.write_all(b" ")?;
+                w.write_all(name.as_ref())
+            }
+        }
+    }
+}
+
+impl From<u32> for GameTypeFilter<'_> {
+    fn from(number: u32) -> Self {
+        GameTypeFilter::Numeric(number)
+    }
+}
+
+impl<'a> From<GameType<'a>> for GameTypeFilter<'a> {
+    fn from(name: GameType<'a>) -> Self {
+        GameTypeFilter::Named(name)
+    }
+}
+
+/// Filters of a "getservers", written in the order `empty`, `full`, then
+/// the gametype, or with the gametype first if `gametype_first` is set.
+///
+/// `empty` and `full` include servers that masters otherwise leave out,
+/// so the default filter lists neither empty nor full servers, see
+/// [`GetServersFilter::all`].
+#[derive(Debug, Default)]
+pub struct GetServersFilter<'a> {
+    /// Include empty servers.
     pub empty: bool,
+    /// Include full servers.
     pub full: bool,
-    pub gametype: Option<Cow<'a, [u8]>>,
+    pub gametype: Option<GameTypeFilter<'a>>,
+    /// Write the gametype before the other filters, for masters that
+    /// require it.
+    pub gametype_first: bool,
 }
-impl GetServersFilter<'_> {
-    fn write_all_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+impl<'a> GetServersFilter<'a> {
+    pub fn builder() -> GetServersFilterBuilder<'a> {
+        GetServersFilterBuilder::default()
+    }
+
+    /// Include empty and full servers, to list every server of the game.
+    pub fn all() -> GetServersFilter<'a> {
+        GetServersFilter {
+            empty: true,
+            full: true,
+            ..Default::default()
+        }
+    }
+
+    /// Read the filter tokens of a parsed request. Any token but `empty`
+    /// and `full` is the gametype, the last one if there are several.
+    #[cfg(feature = "std")]
+    fn from_tokens(tokens: Vec<&'a [u8]>) -> GetServersFilter<'a> {
+        let mut filter = GetServersFilter::default();
+        for token in tokens {
+            match token {
+                b"empty" => filter.empty = true,
+                b"full" => filter.full = true,
+                _ => {
+                    filter.gametype = Some(GameTypeFilter::from_token(token));
+                    continue;
+                }
+            }
+            filter.gametype_first |= filter.gametype.is_some();
+        }
+        filter
+    }
+
+    /// Copy the bytes if borrowed, to keep the value around.
+    pub fn into_owned(self) -> GetServersFilter<'static> {
+        GetServersFilter {
+            empty: self.empty,
+            full: self.full,
+            gametype: self.gametype.map(GameTypeFilter::into_owned),
+            gametype_first: self.gametype_first,
+        }
+    }
+
+    fn write_to_sink<S: Sink>(&self, mut w: S) -> Result<(), S::Error> {
+        if let (true, Some(game_type)) = (self.gametype_first, &self.gametype) {
+            game_type.write_to_sink(&mut w)?;
+        }
         if self.empty {
             w.write_all(b" empty")?;
         }
         if self.full {
             w.write_all(b" full")?;
         }
-        if let Some(ref game_type) = self.gametype {
-            w.write_all(b" ")?;
-            w.write_all(game_type.as_ref())?;
+        if let (false, Some(game_type)) = (self.gametype_first, &self.gametype) {
+            game_type.write_to_sink(&mut w)?;
         }
         Ok(())
     }
 }
+
+#[derive(Debug, Default)]
+pub struct GetServersFilterBuilder<'a> {
+    filter: GetServersFilter<'a>,
+}
+impl<'a> GetServersFilterBuilder<'a> {
+    /// Include empty servers.
+    pub fn empty(mut self) -> Self {
+        self.filter.empty = true;
+        self
+    }
+
+    /// Include full servers.
+    pub fn full(mut self) -> Self {
+        self.filter.full = true;
+        self
+    }
+
+    pub fn gametype(mut self, gametype: impl Into<GameTypeFilter<'a>>) -> Self {
+        self.filter.gametype = Some(gametype.into());
+        self
+    }
+
+    /// Write the gametype before the other filters.
+    pub fn gametype_first(mut self) -> Self {
+        self.filter.gametype_first = true;
+        self
+    }
+
+    pub fn build(self) -> GetServersFilter<'a> {
+        self.filter
+    }
+}
+
 /// A "getservers" message is sent to a master by a client who wants
 /// to get a list of servers. It triggers a "getserversReponse"
 /// message from the master.
+///
+/// The minimal Quake3 form, with neither a game name nor filters:
+///
+/// ```
+/// use dpmaster::{GetServers, ProtocolVersion};
+///
+/// let request = GetServers {
+///     game_name: None,
+///     protocol_version: ProtocolVersion::try_from("68").unwrap(),
+///     filter: Default::default(),
+/// };
+/// let mut buf = [0; 64];
+/// let len = request.write_to_slice(&mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"\xFF\xFF\xFF\xFFgetservers 68");
+/// ```
 #[doc(alias = "getservers")]
 #[derive(Debug)]
 pub struct GetServers<'a> {
+    /// The game to filter on. When `None`, the game name is omitted
+    /// entirely and the Quake3 form `getservers <protocol> [filters]`
+    /// is sent, which some masters treat as the default game.
     pub game_name: Option<GameName<'a>>,
     pub protocol_version: ProtocolVersion<'a>,
     pub filter: GetServersFilter<'a>,
 }
 
 impl GetServers<'_> {
-    pub fn write_all_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetServers<'_>> {
+        final_parser(
+            parse::getservers.map(|(game_name, protocol_version, filters)| GetServers {
+                game_name,
+                protocol_version,
+                filter: GetServersFilter::from_tokens(filters),
+            }),
+        )(bytes)
+    }
+
+    /// Copy the bytes if borrowed, to keep the value around.
+    pub fn into_owned(self) -> GetServers<'static> {
+        GetServers {
+            game_name: self.game_name.map(GameName::into_owned),
+            protocol_version: self.protocol_version.into_owned(),
+            filter: self.filter.into_owned(),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
         writer.write_all(OOB)?;
         writer.write_all(b"getservers")?;
         if let Some(ref game_name) = self.game_name {
@@ -279,48 +1277,251 @@ impl GetServers<'_> {
         }
         writer.write_all(b" ")?;
         writer.write_all(self.protocol_version.as_ref())?;
-        self.filter.write_all_to(&mut writer)?;
-        Ok(())
+        self.filter.write_to_sink(&mut writer)?;
+        Ok(writer.written)
+    }
+}
+
+/// Whether `ip` can be reached from the internet, as opposed to the
+/// private (RFC 1918), shared (CGNAT, `100.64.0.0/10`), unique local,
+/// loopback, link-local, unspecified, "this network" (`0.0.0.0/8`),
+/// documentation, multicast or broadcast addresses of misconfigured
+/// servers. Used by `retain_routable`.
+pub fn is_routable(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_routable_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_routable_v4(ip),
+            None => is_routable_v6(ip),
+        },
     }
 }
 
+fn is_routable_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    let is_shared = a == 100 && b & 0b1100_0000 == 0b0100_0000;
+    !(ip.is_private()
+        || is_shared
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || a == 0
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || ip.is_broadcast())
+}
+
+fn is_routable_v6(ip: Ipv6Addr) -> bool {
+    let is_documentation = ip.segments()[..2] == [0x2001, 0xdb8];
+    !(ip.is_unique_local()
+        || ip.is_loopback()
+        || ip.is_unicast_link_local()
+        || ip.is_unspecified()
+        || is_documentation
+        || ip.is_multicast())
+}
+
 /// A "getserversResponse" message contains a list of IPv4 servers
 /// requested by a client.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[doc(alias = "getserversResponse")]
 pub struct GetServersResponse {
     pub addresses: Vec<SocketAddrV4>,
+    /// Whether the list ended with an EOT. A master with no servers
+    /// replies with only the EOT, giving no `addresses` and `eot` set.
     pub eot: bool,
+    /// Number of IPv6 entries skipped, which some buggy masters mix in.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skipped: usize,
 }
 
 impl GetServersResponse {
-    pub fn parse(bytes: &[u8]) -> ParseResult<GetServersResponse> {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetServersResponse> {
         final_parser(parse::getserversResponse.map(|o| GetServersResponse {
             addresses: o.0,
             eot: o.1 .0,
+            skipped: o.2,
         }))(bytes)
     }
+
+    /// Write the message in a single datagram, with an EOT if `eot` is set.
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(OOB)?;
+        writer.write_all(b"getserversResponse")?;
+        for addr in &self.addresses {
+            encode::write_socket_addr_v4(&mut writer, addr)?;
+        }
+        if self.eot {
+            writer.write_all(EOT)?;
+        }
+        Ok(writer.written)
+    }
+
+    /// Split the message into datagrams of at most `mtu` bytes, with an
+    /// EOT only at the end of the last one, as a master would send it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mtu` can't fit a datagram with one address and an EOT.
+    pub fn write_datagrams(&self, mtu: usize) -> Vec<Vec<u8>> {
+        const HEADER_LEN: usize = OOB.len() + b"getserversResponse".len();
+        const ADDR_LEN: usize = 7;
+        assert!(
+            mtu >= HEADER_LEN + ADDR_LEN + EOT.len(),
+            "mtu is too small to fit an address"
+        );
+        let per_datagram = (mtu - HEADER_LEN - EOT.len()) / ADDR_LEN;
+        let chunks: Vec<&[SocketAddrV4]> = if self.addresses.is_empty() {
+            vec![&[]]
+        } else {
+            self.addresses.chunks(per_datagram).collect()
+        };
+
+        let last = chunks.len() - 1;
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut datagram = vec![];
+                GetServersResponse {
+                    addresses: chunk.to_vec(),
+                    eot: i == last,
+                    skipped: 0,
+                }
+                .write_to_sink(&mut datagram)
+                .expect("writing to a Vec never fails");
+                datagram
+            })
+            .collect()
+    }
+
+    /// Remove duplicated addresses, keeping the first occurrence of each.
+    #[cfg(feature = "std")]
+    pub fn dedup(&mut self) {
+        let mut seen = HashSet::new();
+        self.addresses.retain(|addr| seen.insert(*addr));
+    }
+
+    /// Remove the addresses that can't be reached from the internet, see
+    /// [`is_routable`].
+    pub fn retain_routable(&mut self) {
+        self.addresses.retain(|addr| is_routable_v4(*addr.ip()));
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = SocketAddrV4> + '_ {
+        self.addresses.iter().copied()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn as_set(&self) -> HashSet<SocketAddrV4> {
+        self.addresses.iter().copied().collect()
+    }
+
+    /// Addresses added since `previous`, and the ones removed from it, in
+    /// their order of appearance.
+    #[cfg(feature = "std")]
+    pub fn diff(&self, previous: &Self) -> (Vec<SocketAddrV4>, Vec<SocketAddrV4>) {
+        let current = self.as_set();
+        let previous_set = previous.as_set();
+        let added = self
+            .addresses
+            .iter()
+            .filter(|addr| !previous_set.contains(addr))
+            .copied()
+            .collect();
+        let removed = previous
+            .addresses
+            .iter()
+            .filter(|addr| !current.contains(addr))
+            .copied()
+            .collect();
+        (added, removed)
+    }
 }
 
+/// Filters of a "getserversExt", written in the order `empty`, `full`,
+/// the gametype, `ipv4`, then `ipv6`, or with the gametype first if
+/// `gametype_first` is set.
+///
+/// Like for [`GetServersFilter`], the default filter lists neither empty
+/// nor full servers, see [`GetServersExtFilter::all`].
 #[derive(Debug, Default)]
 pub struct GetServersExtFilter<'a> {
+    /// Include empty servers.
     pub empty: bool,
+    /// Include full servers.
     pub full: bool,
-    pub gametype: Option<Cow<'a, [u8]>>,
+    pub gametype: Option<GameTypeFilter<'a>>,
     pub ipv4: bool,
     pub ipv6: bool,
+    /// Write the gametype before the other filters, for masters that
+    /// require it.
+    pub gametype_first: bool,
 }
-impl GetServersExtFilter<'_> {
-    fn write_all_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+impl<'a> GetServersExtFilter<'a> {
+    pub fn builder() -> GetServersExtFilterBuilder<'a> {
+        GetServersExtFilterBuilder::default()
+    }
+
+    /// Include empty and full servers, of both address families unless
+    /// `ipv4` or `ipv6` is set.
+    pub fn all() -> GetServersExtFilter<'a> {
+        GetServersExtFilter {
+            empty: true,
+            full: true,
+            ..Default::default()
+        }
+    }
+
+    /// Read the filter tokens of a parsed request, as
+    /// [`GetServersFilter`] does besides `ipv4` and `ipv6`.
+    #[cfg(feature = "std")]
+    fn from_tokens(tokens: Vec<&'a [u8]>) -> GetServersExtFilter<'a> {
+        let (families, tokens): (Vec<&[u8]>, _) = tokens
+            .into_iter()
+            .partition(|token| matches!(*token, b"ipv4" | b"ipv6"));
+        let filter = GetServersFilter::from_tokens(tokens);
+        GetServersExtFilter {
+            empty: filter.empty,
+            full: filter.full,
+            gametype: filter.gametype,
+            ipv4: families.contains(&&b"ipv4"[..]),
+            ipv6: families.contains(&&b"ipv6"[..]),
+            gametype_first: filter.gametype_first,
+        }
+    }
+
+    /// Copy the bytes if borrowed, to keep the value around.
+    pub fn into_owned(self) -> GetServersExtFilter<'static> {
+        GetServersExtFilter {
+            empty: self.empty,
+            full: self.full,
+            gametype: self.gametype.map(GameTypeFilter::into_owned),
+            ipv4: self.ipv4,
+            ipv6: self.ipv6,
+            gametype_first: self.gametype_first,
+        }
+    }
+
+    fn write_to_sink<S: Sink>(&self, mut w: S) -> Result<(), S::Error> {
+        if let (true, Some(game_type)) = (self.gametype_first, &self.gametype) {
+            game_type.write_to_sink(&mut w)?;
+        }
         if self.empty {
             w.write_all(b" empty")?;
         }
         if self.full {
             w.write_all(b" full")?;
         }
-        if let Some(game_type) = &self.gametype {
-            w.write_all(b" ")?;
-            w.write_all(game_type.as_ref())?;
+        if let (false, Some(game_type)) = (self.gametype_first, &self.gametype) {
+            game_type.write_to_sink(&mut w)?;
         }
         if self.ipv4 {
             w.write_all(b" ipv4")?;
@@ -332,6 +1533,51 @@ impl GetServersExtFilter<'_> {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct GetServersExtFilterBuilder<'a> {
+    filter: GetServersExtFilter<'a>,
+}
+impl<'a> GetServersExtFilterBuilder<'a> {
+    /// Include empty servers.
+    pub fn empty(mut self) -> Self {
+        self.filter.empty = true;
+        self
+    }
+
+    /// Include full servers.
+    pub fn full(mut self) -> Self {
+        self.filter.full = true;
+        self
+    }
+
+    pub fn gametype(mut self, gametype: impl Into<GameTypeFilter<'a>>) -> Self {
+        self.filter.gametype = Some(gametype.into());
+        self
+    }
+
+    /// Write the gametype before the other filters.
+    pub fn gametype_first(mut self) -> Self {
+        self.filter.gametype_first = true;
+        self
+    }
+
+    /// Include IPv4 servers.
+    pub fn ipv4(mut self) -> Self {
+        self.filter.ipv4 = true;
+        self
+    }
+
+    /// Include IPv6 servers.
+    pub fn ipv6(mut self) -> Self {
+        self.filter.ipv6 = true;
+        self
+    }
+
+    pub fn build(self) -> GetServersExtFilter<'a> {
+        self.filter
+    }
+}
+
 /// A "getserversExt" message is sent to a master by a client who wants
 /// to get a list of servers. It triggers a "getserversExtReponse"
 /// message from the master.
@@ -343,21 +1589,48 @@ pub struct GetServersExt<'a> {
     pub filter: GetServersExtFilter<'a>,
 }
 impl GetServersExt<'_> {
-    pub fn write_all_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetServersExt<'_>> {
+        final_parser(
+            parse::getserversExt.map(|(game_name, protocol_version, filters)| GetServersExt {
+                game_name,
+                protocol_version,
+                filter: GetServersExtFilter::from_tokens(filters),
+            }),
+        )(bytes)
+    }
+
+    /// Copy the bytes if borrowed, to keep the value around.
+    pub fn into_owned(self) -> GetServersExt<'static> {
+        GetServersExt {
+            game_name: self.game_name.into_owned(),
+            protocol_version: self.protocol_version.into_owned(),
+            filter: self.filter.into_owned(),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
         writer.write_all(OOB)?;
         writer.write_all(b"getserversExt")?;
         writer.write_all(b" ")?;
         writer.write_all(self.game_name.as_ref())?;
         writer.write_all(b" ")?;
         writer.write_all(self.protocol_version.as_ref())?;
-        self.filter.write_all_to(&mut writer)?;
-        Ok(())
+        self.filter.write_to_sink(&mut writer)?;
+        Ok(writer.written)
     }
 }
 
 /// A "getserversExtResponse" message contains a list of IPv4 and/or
 /// IPv6 servers requested by a client.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[doc(alias = "getserversExtResponse")]
 pub struct GetServersExtResponse {
     pub addresses: Vec<SocketAddr>,
@@ -365,7 +1638,8 @@ pub struct GetServersExtResponse {
 }
 
 impl GetServersExtResponse {
-    pub fn parse(bytes: &[u8]) -> ParseResult<GetServersExtResponse> {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetServersExtResponse> {
         final_parser(parse::getserversExtResponse.map(|o| GetServersExtResponse {
             addresses: o.0,
             datagram_info: if o.1 .0 {
@@ -375,4 +1649,1483 @@ impl GetServersExtResponse {
             },
         }))(bytes)
     }
+
+    /// Set the scope ID (interface index) of every IPv6 address. The
+    /// protocol doesn't carry it, but link-local addresses need it to be
+    /// reachable on hosts with multiple interfaces.
+    pub fn set_scope_id(&mut self, scope_id: u32) {
+        for addr in &mut self.addresses {
+            if let SocketAddr::V6(addr) = addr {
+                addr.set_scope_id(scope_id);
+            }
+        }
+    }
+
+    /// Write the message in a single datagram, with an EOT only if
+    /// `datagram_info` is [`DatagramInfo::Eot`].
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(OOB)?;
+        writer.write_all(b"getserversExtResponse")?;
+        for addr in &self.addresses {
+            encode::write_socket_addr(&mut writer, addr)?;
+        }
+        if matches!(self.datagram_info, DatagramInfo::Eot) {
+            writer.write_all(EOT)?;
+        }
+        Ok(writer.written)
+    }
+
+    /// Split the message into datagrams of at most `mtu` bytes, with an
+    /// EOT only at the end of the last one, as a master would send it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mtu` can't fit a datagram with one IPv6 address and an
+    /// EOT.
+    pub fn write_datagrams(&self, mtu: usize) -> Vec<Vec<u8>> {
+        const HEADER_LEN: usize = OOB.len() + b"getserversExtResponse".len();
+        const ADDR_V4_LEN: usize = 7;
+        const ADDR_V6_LEN: usize = 19;
+        assert!(
+            mtu >= HEADER_LEN + ADDR_V6_LEN + EOT.len(),
+            "mtu is too small to fit an address"
+        );
+        let capacity = mtu - HEADER_LEN - EOT.len();
+        let mut chunks: Vec<&[SocketAddr]> = vec![];
+        let mut start = 0;
+        let mut len = 0;
+        for (i, addr) in self.addresses.iter().enumerate() {
+            let addr_len = match addr {
+                SocketAddr::V4(_) => ADDR_V4_LEN,
+                SocketAddr::V6(_) => ADDR_V6_LEN,
+            };
+            if len + addr_len > capacity {
+                chunks.push(&self.addresses[start..i]);
+                start = i;
+                len = 0;
+            }
+            len += addr_len;
+        }
+        chunks.push(&self.addresses[start..]);
+
+        let last = chunks.len() - 1;
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut datagram = vec![];
+                GetServersExtResponse {
+                    addresses: chunk.to_vec(),
+                    datagram_info: if i == last {
+                        DatagramInfo::Eot
+                    } else {
+                        DatagramInfo::Single
+                    },
+                }
+                .write_to_sink(&mut datagram)
+                .expect("writing to a Vec never fails");
+                datagram
+            })
+            .collect()
+    }
+
+    /// Remove duplicated addresses, keeping the first occurrence of each.
+    #[cfg(feature = "std")]
+    pub fn dedup(&mut self) {
+        let mut seen = HashSet::new();
+        self.addresses.retain(|addr| seen.insert(*addr));
+    }
+
+    /// Remove the addresses that can't be reached from the internet, see
+    /// [`is_routable`].
+    pub fn retain_routable(&mut self) {
+        self.addresses.retain(|addr| is_routable(addr.ip()));
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.addresses.iter().copied()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn as_set(&self) -> HashSet<SocketAddr> {
+        self.addresses.iter().copied().collect()
+    }
+
+    /// Addresses added since `previous`, and the ones removed from it, in
+    /// their order of appearance.
+    #[cfg(feature = "std")]
+    pub fn diff(&self, previous: &Self) -> (Vec<SocketAddr>, Vec<SocketAddr>) {
+        let current = self.as_set();
+        let previous_set = previous.as_set();
+        let added = self
+            .addresses
+            .iter()
+            .filter(|addr| !previous_set.contains(addr))
+            .copied()
+            .collect();
+        let removed = previous
+            .addresses
+            .iter()
+            .filter(|addr| !current.contains(addr))
+            .copied()
+            .collect();
+        (added, removed)
+    }
+
+    pub fn ipv4_addresses(&self) -> impl Iterator<Item = SocketAddrV4> + '_ {
+        self.addresses.iter().filter_map(|addr| match addr {
+            SocketAddr::V4(addr) => Some(*addr),
+            SocketAddr::V6(_) => None,
+        })
+    }
+
+    pub fn ipv6_addresses(&self) -> impl Iterator<Item = SocketAddrV6> + '_ {
+        self.addresses.iter().filter_map(|addr| match addr {
+            SocketAddr::V4(_) => None,
+            SocketAddr::V6(addr) => Some(*addr),
+        })
+    }
+
+    /// Number of IPv4 and IPv6 addresses.
+    pub fn counts(&self) -> (usize, usize) {
+        let ipv4 = self.addresses.iter().filter(|addr| addr.is_ipv4()).count();
+        (ipv4, self.addresses.len() - ipv4)
+    }
+
+    /// Turn IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) into IPv4
+    /// addresses. Call [`GetServersExtResponse::dedup`] afterwards to drop
+    /// servers that were listed under both families.
+    pub fn normalize_mapped(&mut self) {
+        for addr in &mut self.addresses {
+            if let SocketAddr::V6(v6) = addr {
+                if let Some(ip) = v6.ip().to_ipv4_mapped() {
+                    *addr = SocketAddr::V4(SocketAddrV4::new(ip, v6.port()));
+                }
+            }
+        }
+    }
+}
+
+/// A "getmotd" message is sent to a master by a client who wants to
+/// get the message of the day. It triggers a "motd" message from the
+/// master.
+#[doc(alias = "getmotd")]
+#[derive(Debug)]
+pub struct GetMotd<'a> {
+    pub challenge: Challenge<'a>,
+}
+
+impl GetMotd<'_> {
+    pub fn new(challenge: Challenge<'_>) -> GetMotd<'_> {
+        GetMotd { challenge }
+    }
+
+    /// Other keys of the infostring than `challenge` are ignored.
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, GetMotd<'_>> {
+        final_parser(parse::getmotd.map(|challenge| GetMotd { challenge }))(bytes)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(OOB)?;
+        writer.write_all(b"getmotd")?;
+        writer.write_all(b" \"\\challenge\\")?;
+        writer.write_all(self.challenge.as_ref())?;
+        writer.write_all(b"\"")?;
+        Ok(writer.written)
+    }
+}
+
+/// A "motd" message is the response to a "getmotd" request. Its
+/// infostring contains the challenge and the message of the day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[doc(alias = "motd")]
+pub struct Motd {
+    pub key_values: InfoString,
+}
+
+impl Motd {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, Motd> {
+        final_parser(parse::motd.map(|key_values| Motd {
+            key_values: key_values.into(),
+        }))(bytes)
+    }
+
+    /// Value of the `motd` key.
+    pub fn motd(&self) -> Option<&[u8]> {
+        self.key_values.get(b"motd")
+    }
+
+    /// Value of the `challenge` key.
+    pub fn challenge(&self) -> Option<&[u8]> {
+        self.key_values.get(b"challenge")
+    }
+}
+
+/// A "rcon" message runs a console command on a server, given its remote
+/// console password. The server usually answers with a "print" holding
+/// the output of the command.
+#[doc(alias = "rcon")]
+#[derive(Debug)]
+pub struct Rcon<'a> {
+    pub password: RconPassword<'a>,
+    pub command: ProtocolString<'a>,
+}
+
+impl<'a> Rcon<'a> {
+    pub fn new(password: RconPassword<'a>, command: ProtocolString<'a>) -> Rcon<'a> {
+        Rcon { password, command }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_all_to<W: Write>(&self, writer: W) -> io::Result<usize> {
+        self.write_to_sink(IoSink(writer))
+    }
+
+    fn write_to_sink<S: Sink>(&self, writer: S) -> Result<usize, S::Error> {
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(OOB)?;
+        writer.write_all(b"rcon ")?;
+        writer.write_all(self.password.get())?;
+        writer.write_all(b" ")?;
+        writer.write_all(self.command.get())?;
+        Ok(writer.written)
+    }
+}
+
+/// A "print" message carries text for the client to display, such as the
+/// output of a [`Rcon`] command or why a connection is refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[doc(alias = "print")]
+pub struct Print {
+    /// Everything after the tag, possibly several lines.
+    pub text: Vec<u8>,
+}
+
+/// The text, with invalid UTF-8 replaced.
+impl fmt::Display for Print {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.text))
+    }
+}
+
+impl Print {
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> ParseResult<'_, Print> {
+        final_parser(parse::print.map(|text| Print {
+            text: text.to_vec(),
+        }))(bytes)
+    }
+}
+
+/// Any message that can be parsed, to dispatch a datagram of unknown
+/// kind received by a listener.
+#[derive(Debug)]
+pub enum Message<'a> {
+    GetInfo(GetInfo<'a>),
+    InfoResponse(InfoResponse),
+    GetStatus(GetStatus<'a>),
+    StatusResponse(StatusResponse),
+    GetChallenge(GetChallenge),
+    ChallengeResponse(ChallengeResponse),
+    HeartBeat(HeartBeat<'a>),
+    GetServers(GetServers<'a>),
+    GetServersResponse(GetServersResponse),
+    GetServersExt(GetServersExt<'a>),
+    GetServersExtResponse(GetServersExtResponse),
+    GetMotd(GetMotd<'a>),
+    Motd(Motd),
+    Print(Print),
+}
+
+impl Message<'_> {
+    /// Copy the bytes the message borrows, to keep it around.
+    pub fn into_owned(self) -> Message<'static> {
+        match self {
+            Message::GetInfo(m) => Message::GetInfo(GetInfo::new(m.challenge.into_owned())),
+            Message::InfoResponse(m) => Message::InfoResponse(m),
+            Message::GetStatus(m) => Message::GetStatus(GetStatus::new(m.challenge.into_owned())),
+            Message::StatusResponse(m) => Message::StatusResponse(m),
+            Message::GetChallenge(m) => Message::GetChallenge(m),
+            Message::ChallengeResponse(m) => Message::ChallengeResponse(m),
+            Message::HeartBeat(m) => Message::HeartBeat(HeartBeat {
+                protocol_string: m.protocol_string.into_owned(),
+            }),
+            Message::GetServers(m) => Message::GetServers(m.into_owned()),
+            Message::GetServersResponse(m) => Message::GetServersResponse(m),
+            Message::GetServersExt(m) => Message::GetServersExt(m.into_owned()),
+            Message::GetServersExtResponse(m) => Message::GetServersExtResponse(m),
+            Message::GetMotd(m) => Message::GetMotd(GetMotd::new(m.challenge.into_owned())),
+            Message::Motd(m) => Message::Motd(m),
+            Message::Print(m) => Message::Print(m),
+        }
+    }
+
+    /// Read the command after the out-of-band prefix, up to a space, a
+    /// new line or the `\\` or `/` of a first entry, and parse the rest of
+    /// the datagram as that message.
+    ///
+    /// Fails with [`ParseResponseError::UnexpectedMessage`] if the
+    /// datagram isn't out-of-band or the command is unknown.
+    #[cfg(feature = "std")]
+    pub fn parse(bytes: &[u8]) -> Result<Message<'_>, ParseResponseError> {
+        let command = bytes
+            .strip_prefix(OOB)
+            .ok_or(ParseResponseError::UnexpectedMessage)?
+            .split(|b| matches!(b, b' ' | b'\n' | b'\\' | b'/'))
+            .next()
+            .unwrap_or_default();
+        let message = match command {
+            b"getinfo" => GetInfo::parse(bytes).map(Message::GetInfo),
+            b"infoResponse" => InfoResponse::parse(bytes).map(Message::InfoResponse),
+            b"getstatus" => GetStatus::parse(bytes).map(Message::GetStatus),
+            b"statusResponse" => StatusResponse::parse(bytes).map(Message::StatusResponse),
+            b"getchallenge" => GetChallenge::parse(bytes).map(Message::GetChallenge),
+            b"challengeResponse" => ChallengeResponse::parse(bytes).map(Message::ChallengeResponse),
+            b"heartbeat" => HeartBeat::parse(bytes).map(Message::HeartBeat),
+            b"getservers" => GetServers::parse(bytes).map(Message::GetServers),
+            b"getserversResponse" => {
+                GetServersResponse::parse(bytes).map(Message::GetServersResponse)
+            }
+            b"getserversExt" => GetServersExt::parse(bytes).map(Message::GetServersExt),
+            b"getserversExtResponse" => {
+                GetServersExtResponse::parse(bytes).map(Message::GetServersExtResponse)
+            }
+            b"getmotd" => GetMotd::parse(bytes).map(Message::GetMotd),
+            b"motd" => Motd::parse(bytes).map(Message::Motd),
+            b"print" => Print::parse(bytes).map(Message::Print),
+            _ => return Err(ParseResponseError::UnexpectedMessage),
+        };
+        message.map_err(|e| ParseResponseError::from_error_tree(bytes, e))
+    }
+}
+
+/// Split a stream of bytes, such as a tunneled or reassembled transport,
+/// into messages.
+///
+/// A message ends where the out-of-band prefix of the next one starts, or
+/// at the end of the stream once [`MessageDecoder::finish`] is called.
+/// Bytes before the first prefix are discarded. A prefix only starts the
+/// next message when a known command follows it, so one appearing inside
+/// a message, e.g. the address `255.255.255.255`, is kept in it.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct MessageDecoder {
+    buf: Vec<u8>,
+    /// Start of the bytes not yet returned in `buf`.
+    pos: usize,
+    finished: bool,
+}
+
+#[cfg(feature = "std")]
+impl MessageDecoder {
+    pub fn new() -> MessageDecoder {
+        MessageDecoder::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Mark the end of the stream, so the last buffered message is
+    /// complete.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// The next complete message, or `None` if more bytes are needed.
+    pub fn next_message(&mut self) -> Option<Result<Message<'_>, ParseResponseError>> {
+        let Some(offset) = find_oob(&self.buf[self.pos..]) else {
+            // Keep what may be the beginning of a prefix.
+            self.pos = self.buf.len().saturating_sub(OOB.len() - 1).max(self.pos);
+            return None;
+        };
+        let start = self.pos + offset;
+        let Some(end) = self.message_end(start) else {
+            self.pos = start;
+            return None;
+        };
+        self.pos = end;
+        Some(Message::parse(&self.buf[start..end]))
+    }
+
+    /// The start of the next prefix followed by a known command, or the
+    /// end of a finished stream. `None` if more bytes are needed to tell.
+    fn message_end(&self, start: usize) -> Option<usize> {
+        let mut from = start + OOB.len();
+        while let Some(offset) = find_oob(&self.buf[from..]) {
+            let next = from + offset;
+            let rest = &self.buf[next + OOB.len()..];
+            let command_len = rest
+                .iter()
+                .position(|b| matches!(b, b' ' | b'\n' | b'\\' | b'/' | 0xFF));
+            match command_len {
+                Some(len) if COMMANDS.contains(&&rest[..len]) => return Some(next),
+                None if self.finished && COMMANDS.contains(&rest) => return Some(next),
+                None if !self.finished && COMMANDS.iter().any(|c| c.starts_with(rest)) => {
+                    return None
+                }
+                _ => from = next + 1,
+            }
+        }
+        self.finished.then_some(self.buf.len())
+    }
+}
+
+/// Commands of the messages [`Message::parse`] knows.
+#[cfg(feature = "std")]
+const COMMANDS: [&[u8]; 14] = [
+    b"getinfo",
+    b"infoResponse",
+    b"getstatus",
+    b"statusResponse",
+    b"getchallenge",
+    b"challengeResponse",
+    b"heartbeat",
+    b"getservers",
+    b"getserversResponse",
+    b"getserversExt",
+    b"getserversExtResponse",
+    b"getmotd",
+    b"motd",
+    b"print",
+];
+
+#[cfg(feature = "std")]
+fn find_oob(bytes: &[u8]) -> Option<usize> {
+    bytes.windows(OOB.len()).position(|w| w == OOB)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn oob(message: &[u8]) -> Vec<u8> {
+        [OOB, message].concat()
+    }
+
+    fn kind(message: &Message) -> &'static str {
+        match message {
+            Message::GetInfo(_) => "getinfo",
+            Message::InfoResponse(_) => "infoResponse",
+            Message::GetStatus(_) => "getstatus",
+            Message::StatusResponse(_) => "statusResponse",
+            Message::GetChallenge(_) => "getchallenge",
+            Message::ChallengeResponse(_) => "challengeResponse",
+            Message::HeartBeat(_) => "heartbeat",
+            Message::GetServers(_) => "getservers",
+            Message::GetServersResponse(_) => "getserversResponse",
+            Message::GetServersExt(_) => "getserversExt",
+            Message::GetServersExtResponse(_) => "getserversExtResponse",
+            Message::GetMotd(_) => "getmotd",
+            Message::Motd(_) => "motd",
+            Message::Print(_) => "print",
+        }
+    }
+
+    #[test]
+    fn message_parse_classifies_each_message() {
+        let ipv6_entry = [
+            &b"/"[..],
+            &"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets(),
+            &27960u16.to_be_bytes(),
+        ]
+        .concat();
+        let cases = [
+            (oob(b"getinfo abc"), "getinfo"),
+            (oob(b"infoResponse\n\\a\\b"), "infoResponse"),
+            (oob(b"getstatus abc"), "getstatus"),
+            (
+                oob(b"statusResponse\n\\a\\b\n1 2 \"x\"\n"),
+                "statusResponse",
+            ),
+            (oob(b"challengeResponse 123"), "challengeResponse"),
+            (oob(b"heartbeat DarkPlaces\n"), "heartbeat"),
+            (
+                oob(b"getserversResponse\\\x01\x02\x03\x04\x6d\x38\\EOT\0\0\0"),
+                "getserversResponse",
+            ),
+            (
+                [&oob(b"getserversExtResponse")[..], &ipv6_entry, EOT].concat(),
+                "getserversExtResponse",
+            ),
+            (oob(b"motd \"\\challenge\\abc\\motd\\hi\""), "motd"),
+            (oob(b"print\nhi"), "print"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(kind(&Message::parse(&bytes).unwrap()), expected);
+        }
+    }
+
+    #[test]
+    fn message_parse_rejects_unknown_command() {
+        assert_eq!(
+            Message::parse(&oob(b"unknown abc")).unwrap_err(),
+            ParseResponseError::UnexpectedMessage
+        );
+        assert_eq!(
+            Message::parse(b"getinfo abc").unwrap_err(),
+            ParseResponseError::UnexpectedMessage
+        );
+    }
+
+    #[test]
+    fn message_parse_ext_response_starting_with_ipv6() {
+        let bytes = [
+            &oob(b"getserversExtResponse/")[..],
+            &"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets(),
+            &27960u16.to_be_bytes(),
+            EOT,
+        ]
+        .concat();
+        let Ok(Message::GetServersExtResponse(response)) = Message::parse(&bytes) else {
+            panic!("not classified as getserversExtResponse");
+        };
+        assert_eq!(
+            response.addresses,
+            vec!["[2001:db8::1]:27960".parse::<SocketAddr>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn parse_response_error_variants() {
+        let bytes = oob(b"infoResponse\n\\a\\b\nextra");
+        let error = InfoResponse::parse(&bytes).unwrap_err();
+        assert_eq!(
+            ParseResponseError::from_error_tree(&bytes, error),
+            ParseResponseError::TrailingData(5)
+        );
+
+        let bytes = oob(b"statusResponse\n\\a\\b\n");
+        let error = InfoResponse::parse(&bytes).unwrap_err();
+        assert_eq!(
+            ParseResponseError::from_error_tree(&bytes, error),
+            ParseResponseError::UnexpectedMessage
+        );
+
+        let bytes = oob(b"statusResponse\n\\a\\b\n0 50 \"name\" 1\n0 5x \"b\" 1\n");
+        let error = StatusResponse::parse(&bytes).unwrap_err();
+        assert!(matches!(
+            ParseResponseError::from_error_tree(&bytes, error),
+            ParseResponseError::InvalidResponse(_)
+        ));
+
+        let bytes = oob(b"statusResponse\n\\a\\b\n0 50 \"n\" 1\n99999999999 50 \"m\" 1\n");
+        let error = StatusResponse::parse(&bytes).unwrap_err();
+        assert!(matches!(
+            ParseResponseError::from_error_tree(&bytes, error),
+            ParseResponseError::InvalidResponse(_)
+        ));
+    }
+
+    #[test]
+    fn getinfo_and_getstatus_alternate_spelling() {
+        let challenge = || Challenge::try_from("abc").unwrap();
+        let command = Command::try_from("getInfo").unwrap();
+        let mut bytes = vec![];
+        GetInfo::new(challenge())
+            .write_all_with_command_to(&command, &mut bytes)
+            .unwrap();
+        assert_eq!(bytes, oob(b"getInfo abc"));
+
+        let command = Command::try_from("GETSTATUS").unwrap();
+        let mut bytes = vec![];
+        GetStatus::new(challenge())
+            .write_all_with_command_to(&command, &mut bytes)
+            .unwrap();
+        assert_eq!(bytes, oob(b"GETSTATUS abc"));
+
+        let mut bytes = vec![];
+        GetStatus::new(challenge())
+            .write_all_to(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, oob(b"getstatus abc"));
+        assert!(Command::try_from("get info").is_err());
+    }
+
+    #[test]
+    fn getservers_response_with_only_eot() {
+        let response = GetServersResponse::parse(&oob(b"getserversResponse\\EOT\0\0\0")).unwrap();
+        assert_eq!(response.addresses, vec![]);
+        assert!(response.eot);
+
+        let response =
+            GetServersExtResponse::parse(&oob(b"getserversExtResponse\\EOT\0\0\0")).unwrap();
+        assert_eq!(response.addresses, vec![]);
+        assert_eq!(response.datagram_info, DatagramInfo::Eot);
+    }
+
+    #[test]
+    fn rcon_encoding() {
+        let rcon = Rcon::new(
+            RconPassword::try_from("secret").unwrap(),
+            ProtocolString::try_from("kick player 2").unwrap(),
+        );
+        let mut bytes = vec![];
+        let written = rcon.write_all_to(&mut bytes).unwrap();
+        assert_eq!(bytes, oob(b"rcon secret kick player 2"));
+        assert_eq!(written, bytes.len());
+    }
+
+    #[test]
+    fn rcon_password_rejects_whitespace() {
+        assert!(RconPassword::try_from("two words").is_err());
+        assert!(RconPassword::try_from("tab\tbed").is_err());
+        assert!(ProtocolString::try_from("two\nlines").is_err());
+    }
+
+    #[test]
+    fn print_multi_line() {
+        let print = Print::parse(&oob(b"print\nServer is full.\nTry again later.\n")).unwrap();
+        assert_eq!(print.text, b"Server is full.\nTry again later.\n");
+    }
+
+    #[test]
+    fn message_decoder_joins_split_message() {
+        let bytes = oob(b"infoResponse\n\\a\\b");
+        let mut decoder = MessageDecoder::new();
+        decoder.push(&bytes[..10]);
+        assert!(decoder.next_message().is_none());
+        decoder.push(&bytes[10..]);
+        decoder.push(&oob(b"print\nhi"));
+        let Some(Ok(Message::InfoResponse(info))) = decoder.next_message() else {
+            panic!("expected an infoResponse");
+        };
+        assert_eq!(info.get(b"a"), Some(&b"b"[..]));
+        assert!(decoder.next_message().is_none());
+        decoder.finish();
+        assert!(matches!(
+            decoder.next_message(),
+            Some(Ok(Message::Print(_)))
+        ));
+    }
+
+    #[test]
+    fn info_response_keeps_key_order() {
+        let response = InfoResponse::parse(&oob(
+            b"infoResponse\n\\sv_hostname\\test\\clients\\2\\challenge\\abc",
+        ))
+        .unwrap();
+        let keys: Vec<&[u8]> = response.key_values.keys().collect();
+        assert_eq!(keys, [&b"sv_hostname"[..], b"clients", b"challenge"]);
+    }
+
+    #[test]
+    fn info_response_without_keys() {
+        let response = InfoResponse::parse(&oob(b"infoResponse\n")).unwrap();
+        assert!(response.key_values.is_empty());
+    }
+
+    #[test]
+    fn info_response_with_empty_value() {
+        let response =
+            InfoResponse::parse(&oob(b"infoResponse\n\\sv_hostname\\\\clients\\2")).unwrap();
+        assert_eq!(response.get(b"sv_hostname"), Some(&b""[..]));
+        assert_eq!(response.get(b"clients"), Some(&b"2"[..]));
+    }
+
+    #[test]
+    fn getservers_response_round_trip() {
+        let datagram = oob(
+            b"getserversResponse\\\x01\x02\x03\x04\x6d\x38\\\x05\x06\x07\x08\x69\x87\\EOT\0\0\0",
+        );
+        let response = GetServersResponse::parse(&datagram).unwrap();
+        assert_eq!(
+            response.addresses,
+            [
+                "1.2.3.4:27960".parse().unwrap(),
+                "5.6.7.8:27015".parse().unwrap()
+            ]
+        );
+        assert!(response.eot);
+        let mut written = vec![];
+        response.write_all_to(&mut written).unwrap();
+        assert_eq!(written, datagram);
+    }
+
+    #[test]
+    fn getservers_ext_response_round_trip() {
+        let datagram = [
+            &oob(b"getserversExtResponse\\\x01\x02\x03\x04\x6d\x38/")[..],
+            &"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets(),
+            b"\x69\x87\\EOT\0\0\0",
+        ]
+        .concat();
+        let response = GetServersExtResponse::parse(&datagram).unwrap();
+        assert_eq!(
+            response.addresses,
+            [
+                "1.2.3.4:27960".parse().unwrap(),
+                "[2001:db8::1]:27015".parse().unwrap()
+            ]
+        );
+        assert!(matches!(response.datagram_info, DatagramInfo::Eot));
+        let mut written = vec![];
+        response.write_all_to(&mut written).unwrap();
+        assert_eq!(written, datagram);
+    }
+
+    #[test]
+    fn getservers_response_write_datagrams_puts_eot_last() {
+        let response = GetServersResponse {
+            addresses: (0..500)
+                .map(|i| SocketAddrV4::new(Ipv4Addr::new(10, 0, (i >> 8) as u8, i as u8), 27960))
+                .collect(),
+            eot: true,
+            skipped: 0,
+        };
+        let datagrams = response.write_datagrams(1400);
+        assert!(datagrams.len() >= 3);
+        let mut addresses = vec![];
+        for (i, datagram) in datagrams.iter().enumerate() {
+            assert!(datagram.len() <= 1400);
+            let parsed = GetServersResponse::parse(datagram).unwrap();
+            assert_eq!(parsed.eot, i == datagrams.len() - 1);
+            addresses.extend(parsed.addresses);
+        }
+        assert_eq!(addresses, response.addresses);
+    }
+
+    #[test]
+    fn getservers_ext_response_write_datagrams_puts_eot_last() {
+        let response = GetServersExtResponse {
+            addresses: (0..300u16)
+                .map(|i| match i % 2 {
+                    0 => SocketAddr::new(Ipv4Addr::new(10, 0, 0, i as u8).into(), 27960),
+                    _ => SocketAddr::new(
+                        Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, i).into(),
+                        27960,
+                    ),
+                })
+                .collect(),
+            datagram_info: DatagramInfo::Eot,
+        };
+        let datagrams = response.write_datagrams(1400);
+        assert!(datagrams.len() >= 3);
+        let mut addresses = vec![];
+        for (i, datagram) in datagrams.iter().enumerate() {
+            assert!(datagram.len() <= 1400);
+            let parsed = GetServersExtResponse::parse(datagram).unwrap();
+            let is_last = i == datagrams.len() - 1;
+            assert_eq!(matches!(parsed.datagram_info, DatagramInfo::Eot), is_last);
+            addresses.extend(parsed.addresses);
+        }
+        assert_eq!(addresses, response.addresses);
+    }
+
+    #[test]
+    fn getservers_response_with_eot_before_the_last_address() {
+        let datagram = oob(
+            b"getserversResponse\\\x01\x02\x03\x04\x6d\x38\\EOT\0\0\0\\\x05\x06\x07\x08\x69\x87",
+        );
+        let response = GetServersResponse::parse(&datagram).unwrap();
+        assert_eq!(
+            response.addresses,
+            [
+                "1.2.3.4:27960".parse().unwrap(),
+                "5.6.7.8:27015".parse().unwrap()
+            ]
+        );
+        assert!(response.eot);
+
+        let datagram = oob(
+            b"getserversExtResponse\\\x01\x02\x03\x04\x6d\x38\\EOT\0\0\0\\\x05\x06\x07\x08\x69\x87",
+        );
+        let response = GetServersExtResponse::parse(&datagram).unwrap();
+        assert_eq!(response.addresses.len(), 2);
+        assert!(matches!(response.datagram_info, DatagramInfo::Eot));
+    }
+
+    #[test]
+    fn getservers_response_skips_stray_ipv6_entries() {
+        let datagram = [
+            &oob(b"getserversResponse\\\x01\x02\x03\x04\x6d\x38/")[..],
+            &"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets(),
+            b"\x69\x87\\\x05\x06\x07\x08\x69\x87\\EOT\0\0\0",
+        ]
+        .concat();
+        let response = GetServersResponse::parse(&datagram).unwrap();
+        assert_eq!(
+            response.addresses,
+            [
+                "1.2.3.4:27960".parse().unwrap(),
+                "5.6.7.8:27015".parse().unwrap()
+            ]
+        );
+        assert_eq!(response.skipped, 1);
+        assert!(response.eot);
+    }
+
+    #[test]
+    fn status_response_over_max_players_fails() {
+        let bytes =
+            oob(b"statusResponse\n\\sv_hostname\\test\n1 50 \"a\"\n2 50 \"b\"\n3 50 \"c\"\n");
+        let limits = ParseLimits {
+            max_players: 2,
+            ..ParseLimits::NONE
+        };
+        assert!(StatusResponse::parse_with_limits(&bytes, limits).is_err());
+        let limits = ParseLimits {
+            max_players: 3,
+            ..ParseLimits::NONE
+        };
+        let response = StatusResponse::parse_with_limits(&bytes, limits).unwrap();
+        assert_eq!(response.player_infos.len(), 3);
+    }
+
+    #[test]
+    fn info_response_over_max_lengths_fails() {
+        let bytes = oob(b"infoResponse\n\\sv_hostname\\a very long hostname");
+        let limits = ParseLimits {
+            max_value_len: 8,
+            ..ParseLimits::NONE
+        };
+        assert!(InfoResponse::parse_with_limits(&bytes, limits).is_err());
+        assert!(StatusResponse::parse_with_limits(
+            &oob(b"statusResponse\n\\sv_hostname\\a very long hostname"),
+            limits
+        )
+        .is_err());
+        let limits = ParseLimits {
+            max_key_len: 8,
+            ..ParseLimits::NONE
+        };
+        assert!(InfoResponse::parse_with_limits(&bytes, limits).is_err());
+        let limits = ParseLimits {
+            max_key_len: 11,
+            max_value_len: 20,
+            ..ParseLimits::NONE
+        };
+        assert!(InfoResponse::parse_with_limits(&bytes, limits).is_ok());
+    }
+
+    #[test]
+    fn protocol_version_as_u32() {
+        let version = |v: &'static str| ProtocolVersion::new(Cow::Borrowed(v.as_bytes())).unwrap();
+        assert_eq!(version("68").as_u32(), Some(68));
+        assert_eq!(version("0068").as_u32(), Some(68));
+        assert_eq!(version("99999999999999").as_u32(), None);
+        assert_eq!(version("").as_u32(), None);
+        assert!(ProtocolVersion::new_u32(Cow::Borrowed(b"68")).is_ok());
+        assert!(ProtocolVersion::new_u32(Cow::Borrowed(b"0068")).is_ok());
+        assert_eq!(
+            ProtocolVersion::new_u32(Cow::Borrowed(b"99999999999999")).unwrap_err(),
+            NewU32ProtocolVersionError::OutOfRange
+        );
+        assert_eq!(
+            ProtocolVersion::new_u32(Cow::Borrowed(b"6x")).unwrap_err(),
+            NewU32ProtocolVersionError::Invalid(NewProtocolVersionError)
+        );
+    }
+
+    fn get_servers(
+        game_name: Option<&'static str>,
+        filter: GetServersFilter<'static>,
+    ) -> GetServers<'static> {
+        GetServers {
+            game_name: game_name.map(|name| GameName::try_from(name).unwrap()),
+            protocol_version: ProtocolVersion::try_from("68").unwrap(),
+            filter,
+        }
+    }
+
+    fn written(write_all_to: impl FnOnce(&mut Vec<u8>) -> io::Result<usize>) -> Vec<u8> {
+        let mut bytes = vec![];
+        let len = write_all_to(&mut bytes).unwrap();
+        assert_eq!(len, bytes.len());
+        bytes
+    }
+
+    #[test]
+    fn write_to_slice_fits_or_fails() {
+        let request = get_servers(Some("Xonotic"), GetServersFilter::default());
+        let expected = written(|w| request.write_all_to(w));
+        let mut buf = [0; 64];
+        let len = request.write_to_slice(&mut buf).unwrap();
+        assert_eq!(&buf[..len], expected);
+        assert_eq!(
+            request.write_to_slice(&mut buf[..expected.len() - 1]),
+            Err(BufferTooSmallError)
+        );
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn write_to_buf_appends_to_bytes_mut() {
+        let request = get_servers(Some("Xonotic"), GetServersFilter::all());
+        let mut buf = bytes::BytesMut::from(&b"head"[..]);
+        let len = request.write_to_buf(&mut buf).unwrap();
+        assert_eq!(len, buf.len() - 4);
+        assert_eq!(&buf[..4], b"head");
+        assert_eq!(&buf[4..], oob(b"getservers Xonotic 68 empty full"));
+    }
+
+    #[test]
+    fn normalize_mapped_turns_mapped_addresses_into_ipv4() {
+        let mut response = GetServersExtResponse {
+            addresses: vec![
+                "[::ffff:1.2.3.4]:27960".parse().unwrap(),
+                "1.2.3.4:27960".parse().unwrap(),
+                "[2001:db8::1]:27960".parse().unwrap(),
+            ],
+            datagram_info: DatagramInfo::Eot,
+        };
+        response.normalize_mapped();
+        assert_eq!(
+            response.addresses,
+            [
+                SocketAddr::from(([1, 2, 3, 4], 27960)),
+                SocketAddr::from(([1, 2, 3, 4], 27960)),
+                "[2001:db8::1]:27960".parse().unwrap(),
+            ]
+        );
+        response.dedup();
+        assert_eq!(response.counts(), (1, 1));
+    }
+
+    #[test]
+    fn client_requests_round_trip() {
+        let requests: [&[u8]; 6] = [
+            b"getservers Xonotic 3 empty full",
+            b"getservers 68 ctf empty",
+            b"getservers Xonotic 3 empty gametype=4",
+            b"getserversExt Xonotic 3 full ipv6",
+            b"getchallenge",
+            b"getmotd \"\\challenge\\abc\"",
+        ];
+        for request in requests {
+            let request = oob(request);
+            let message = Message::parse(&request).unwrap();
+            let written = written(|w| match message {
+                Message::GetServers(m) => m.write_all_to(w),
+                Message::GetServersExt(m) => m.write_all_to(w),
+                Message::GetChallenge(m) => m.write_all_to(w),
+                Message::GetMotd(m) => m.write_all_to(w),
+                m => panic!("unexpected {}", kind(&m)),
+            });
+            assert_eq!(written, request);
+        }
+    }
+
+    #[test]
+    fn parse_client_request_filters() {
+        let request = oob(b"getservers 68 ctf empty\n");
+        let request = GetServers::parse(&request).unwrap();
+        assert!(request.game_name.is_none());
+        assert!(request.filter.empty && !request.filter.full);
+        assert!(request.filter.gametype_first);
+        assert!(matches!(
+            request.filter.gametype,
+            Some(GameTypeFilter::Named(ref name)) if name.as_ref() == b"ctf"
+        ));
+
+        let request = oob(b"getserversExt Xonotic 3 gametype=4 ipv4");
+        let request = GetServersExt::parse(&request).unwrap().into_owned();
+        assert_eq!(request.game_name.as_ref(), b"Xonotic");
+        assert!(request.filter.ipv4 && !request.filter.ipv6);
+        assert!(!request.filter.gametype_first);
+        assert!(matches!(
+            request.filter.gametype,
+            Some(GameTypeFilter::Numeric(4))
+        ));
+
+        assert!(GetChallenge::parse(&oob(b"getchallenge 1234 Q3A\n")).is_ok());
+        let request = oob(b"getmotd \\challenge\\abc\\clientversion\\1");
+        let request = GetMotd::parse(&request).unwrap();
+        assert_eq!(request.challenge.as_ref(), b"abc");
+        assert!(GetMotd::parse(&oob(b"getmotd \\clientversion\\1")).is_err());
+    }
+
+    #[test]
+    fn message_decoder_keeps_a_prefix_inside_a_message() {
+        let response = GetServersResponse {
+            addresses: vec![
+                "255.255.255.255:27960".parse().unwrap(),
+                "1.2.3.4:26000".parse().unwrap(),
+            ],
+            eot: true,
+            skipped: 0,
+        };
+        let mut bytes = written(|w| response.write_all_to(w));
+        bytes.extend_from_slice(&oob(b"print\nhi"));
+        let split = bytes.len() - b"int\nhi".len();
+
+        let mut decoder = MessageDecoder::new();
+        decoder.push(&bytes[..split]);
+        assert!(decoder.next_message().is_none());
+        decoder.push(&bytes[split..]);
+        let Some(Ok(Message::GetServersResponse(decoded))) = decoder.next_message() else {
+            panic!("expected a getserversResponse");
+        };
+        assert_eq!(decoded, response);
+        decoder.finish();
+        let Some(Ok(Message::Print(print))) = decoder.next_message() else {
+            panic!("expected a print");
+        };
+        assert_eq!(print.text, b"hi");
+        assert!(decoder.next_message().is_none());
+    }
+
+    #[test]
+    fn heartbeat_is_flatline_anywhere_ignoring_case() {
+        let heartbeat = |protocol_string: &'static str| HeartBeat {
+            protocol_string: ProtocolString::try_from(protocol_string).unwrap(),
+        };
+        for register in ["DarkPlaces", "QuakeArena-1", "Wolfenstein-1", "flat"] {
+            assert!(!heartbeat(register).is_flatline(), "{register}");
+        }
+        for deregister in ["flatline-1", "WolfFlatline-1", "ETFlatline-1", "FLATLINE"] {
+            assert!(heartbeat(deregister).is_flatline(), "{deregister}");
+        }
+    }
+
+    #[test]
+    fn parse_partial_returns_the_trailing_bytes_parse_rejects() {
+        let bytes = oob(b"infoResponse\n\\a\\b\nextra");
+        let (info, trailing) = InfoResponse::parse_partial(&bytes).unwrap();
+        assert_eq!(info.get(b"a"), Some(&b"b"[..]));
+        assert_eq!(trailing, b"extra");
+        assert!(InfoResponse::parse(&bytes).is_err());
+
+        let bytes = oob(b"infoResponse\n\\a\\b\n");
+        let (_, trailing) = InfoResponse::parse_partial(&bytes).unwrap();
+        assert!(trailing.is_empty());
+        assert!(InfoResponse::parse(&bytes).is_ok());
+
+        let bytes = oob(b"statusResponse\n\\a\\b\n0 50 \"p\"\nextra line\n");
+        let (status, trailing) = StatusResponse::parse_partial(&bytes).unwrap();
+        assert_eq!(status.player_infos.len(), 1);
+        assert_eq!(trailing, b"extra line\n");
+        let error = StatusResponse::parse(&bytes).unwrap_err();
+        assert_eq!(
+            ParseResponseError::from_error_tree(&bytes, error),
+            ParseResponseError::TrailingData(11)
+        );
+
+        let bytes = oob(b"statusResponse\n\\a\\b\n0 50 \"p\"\n");
+        let (_, trailing) = StatusResponse::parse_partial(&bytes).unwrap();
+        assert!(trailing.is_empty());
+        assert!(StatusResponse::parse(&bytes).is_ok());
+    }
+
+    #[test]
+    fn get_servers_without_game_name_has_single_spaces() {
+        let filters = [
+            GetServersFilter::default(),
+            GetServersFilter::all(),
+            GetServersFilter::builder().gametype(4).build(),
+            GetServersFilter::builder()
+                .full()
+                .gametype(GameType::try_from("ctf").unwrap())
+                .gametype_first()
+                .build(),
+        ];
+        let expected: [&[u8]; 4] = [
+            b"getservers 68",
+            b"getservers 68 empty full",
+            b"getservers 68 gametype=4",
+            b"getservers 68 ctf full",
+        ];
+        for (filter, expected) in filters.into_iter().zip(expected) {
+            let bytes = written(|w| get_servers(None, filter).write_all_to(w));
+            assert_eq!(bytes, oob(expected));
+            assert!(!bytes.windows(2).any(|w| w == b"  "));
+            assert!(!bytes.ends_with(b" "));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        fn round_trip<T>(value: &T) -> String
+        where
+            T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + fmt::Debug,
+        {
+            let json = serde_json::to_string(value).unwrap();
+            assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value);
+            json
+        }
+
+        let info = InfoResponse::parse(&oob(b"infoResponse\n\\sv_hostname\\test")).unwrap();
+        assert!(round_trip(&info).contains(r#""sv_hostname":"test""#));
+        let status = oob(b"statusResponse\n\\sv_hostname\\test\n3 50 \"alice\" 1\n");
+        let status = StatusResponse::parse(&status).unwrap();
+        assert!(round_trip(&status).contains(r#""team":1"#));
+        round_trip(&GetServersResponse {
+            addresses: vec!["1.2.3.4:26000".parse().unwrap()],
+            eot: true,
+            skipped: 1,
+        });
+        round_trip(&GetServersExtResponse {
+            addresses: vec![
+                "1.2.3.4:26000".parse().unwrap(),
+                "[2001:db8::1]:27960".parse().unwrap(),
+            ],
+            datagram_info: DatagramInfo::Eot,
+        });
+    }
+
+    #[test]
+    fn challenge_response_ignores_trailing_data() {
+        let cases: [&[u8]; 5] = [
+            b"challengeResponse 1234",
+            b"challengeResponse 1234\n",
+            b"challengeResponse 1234 -1 68",
+            b"challengeResponse 1234\0\0garbage",
+            b"challengeResponse 1234\n\\extra\\data",
+        ];
+        for bytes in cases {
+            let response = ChallengeResponse::parse(&oob(bytes)).unwrap();
+            assert_eq!(response.challenge, b"1234");
+        }
+        assert!(ChallengeResponse::parse(&oob(b"challengeResponse ")).is_err());
+        assert!(ChallengeResponse::parse(&oob(b"challengeResponse")).is_err());
+    }
+
+    #[test]
+    fn parse_heartbeats() {
+        let cases: [(&[u8], &[u8], bool); 3] = [
+            (b"heartbeat DarkPlaces\n", b"DarkPlaces", false),
+            (b"heartbeat flatline-1\n", b"flatline-1", true),
+            (b"heartbeat QuakeArena-1", b"QuakeArena-1", false),
+        ];
+        for (message, protocol_string, flatline) in cases {
+            let bytes = oob(message);
+            let heartbeat = HeartBeat::parse(&bytes).unwrap();
+            assert_eq!(heartbeat.protocol_string.get().as_ref(), protocol_string);
+            assert_eq!(heartbeat.is_flatline(), flatline);
+
+            let written = written(|w| heartbeat.write_all_to(w));
+            let parsed = HeartBeat::parse(&written).unwrap();
+            assert_eq!(
+                parsed.protocol_string.get(),
+                heartbeat.protocol_string.get()
+            );
+        }
+
+        assert!(HeartBeat::parse(&oob(b"heartbeat DarkPlaces\nextra")).is_err());
+        assert!(HeartBeat::parse(&oob(b"heartbeatDarkPlaces\n")).is_err());
+        assert!(HeartBeat::parse(b"heartbeat DarkPlaces\n").is_err());
+    }
+
+    #[test]
+    fn parse_getinfo_and_getstatus_challenges() {
+        let bytes = oob(b"getinfo abc-123_XYZ");
+        let getinfo = GetInfo::parse(&bytes).unwrap();
+        assert_eq!(&*getinfo.challenge, b"abc-123_XYZ");
+        let bytes = oob(b"getstatus abc-123_XYZ");
+        let getstatus = GetStatus::parse(&bytes).unwrap();
+        assert_eq!(&*getstatus.challenge, b"abc-123_XYZ");
+
+        for forbidden in [b'\\', b'/', b';', b'"', b'%', 0x7F, 0xFF] {
+            let mut getinfo = oob(b"getinfo ab");
+            getinfo.push(forbidden);
+            assert!(GetInfo::parse(&getinfo).is_err(), "{forbidden:#x}");
+            let mut getstatus = oob(b"getstatus ab");
+            getstatus.push(forbidden);
+            assert!(GetStatus::parse(&getstatus).is_err(), "{forbidden:#x}");
+        }
+        assert!(GetInfo::parse(&oob(b"getinfo ")).is_err());
+        assert!(GetStatus::parse(&oob(b"getstatus ab cd")).is_err());
+    }
+
+    #[test]
+    fn status_response_keeps_non_utf8_player_names() {
+        let bytes =
+            oob(b"statusResponse\n\\sv_hostname\\test\n3 50 \"caf\xe9 \xff\" 1\n7 20 \"ok\"\n");
+        let status = StatusResponse::parse(&bytes).unwrap();
+        assert_eq!(status.player_infos.len(), 2);
+        let player = &status.player_infos[0];
+        assert_eq!(player.name, b"caf\xe9 \xff");
+        assert_eq!(player.name_lossy(), "caf\u{FFFD} \u{FFFD}");
+        assert_eq!((player.frags, player.ping, player.team), (3, 50, Some(1)));
+        assert_eq!(status.player_infos[1].name, b"ok");
+
+        let status_ref = StatusResponseRef::parse(&bytes).unwrap();
+        assert_eq!(status_ref.player_infos[0].name, b"caf\xe9 \xff");
+    }
+
+    #[test]
+    fn status_response_with_a_trailing_new_line() {
+        for bytes in [
+            oob(b"statusResponse\n\\sv_hostname\\test"),
+            oob(b"statusResponse\n\\sv_hostname\\test\n"),
+        ] {
+            let status = StatusResponse::parse(&bytes).unwrap();
+            assert_eq!(
+                status.key_values.get(b"sv_hostname"),
+                Some(b"test".as_slice())
+            );
+            assert!(status.player_infos.is_empty());
+        }
+        // Every player line ends with a new line, the last one included.
+        let bytes = oob(b"statusResponse\n\\sv_hostname\\test\n1 50 \"a\"\n");
+        let status = StatusResponse::parse(&bytes).unwrap();
+        assert_eq!(
+            status.key_values.get(b"sv_hostname"),
+            Some(b"test".as_slice())
+        );
+        assert_eq!(status.player_infos.len(), 1);
+        let status_ref = StatusResponseRef::parse(&bytes).unwrap();
+        assert_eq!(status_ref.player_infos[0].name, b"a");
+    }
+
+    #[test]
+    fn info_response_with_a_trailing_new_line() {
+        for bytes in [
+            oob(b"infoResponse\n\\sv_hostname\\test\\clients\\3"),
+            oob(b"infoResponse\n\\sv_hostname\\test\\clients\\3\n"),
+        ] {
+            let info = InfoResponse::parse(&bytes).unwrap();
+            assert_eq!(info.get(b"sv_hostname"), Some(b"test".as_slice()));
+            assert_eq!(info.get(b"clients"), Some(b"3".as_slice()));
+            let (_, trailing) = InfoResponse::parse_partial(&bytes).unwrap();
+            assert!(trailing.is_empty());
+        }
+        assert!(InfoResponse::parse(&oob(b"infoResponse\n\\a\\b\n\n")).is_err());
+    }
+
+    #[test]
+    fn sanitized_challenge_is_valid() {
+        let challenge = Challenge::sanitized(b"hel lo\\");
+        assert_eq!(&*challenge, b"hello");
+        assert!(Challenge::try_from(&*challenge).is_ok());
+        assert!(challenge.iter().all(|&b| Challenge::is_valid_byte(b)));
+        assert!(!Challenge::is_valid_byte(b' '));
+        assert!(!Challenge::is_valid_byte(b'\\'));
+
+        let game_name = GameName::sanitized(b"Quake 3\tArena\n");
+        assert_eq!(&*game_name, b"Quake3Arena");
+        assert!(GameName::try_from(&*game_name).is_ok());
+    }
+
+    #[test]
+    fn retain_routable_drops_private_addresses() {
+        let mut response = GetServersResponse {
+            addresses: [
+                "1.2.3.4:27960",
+                "10.0.0.1:27960",
+                "8.8.8.8:26000",
+                "172.16.5.4:27960",
+                "192.168.1.2:27960",
+                "127.0.0.1:27960",
+                "169.254.0.1:27960",
+                "100.64.0.1:27960",
+                "100.128.0.1:27960",
+                "0.1.2.3:27960",
+                "192.0.2.1:27960",
+                "203.0.113.9:27960",
+                "224.0.0.1:27960",
+                "255.255.255.255:27960",
+            ]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect(),
+            eot: true,
+            skipped: 0,
+        };
+        response.retain_routable();
+        let expected: Vec<SocketAddrV4> = ["1.2.3.4:27960", "8.8.8.8:26000", "100.128.0.1:27960"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        assert_eq!(response.addresses, expected);
+
+        let mut response = GetServersExtResponse {
+            addresses: [
+                "1.2.3.4:27960",
+                "192.168.1.2:27960",
+                "[2a00:1450::1]:27960",
+                "[fd00::1]:27960",
+                "[::1]:27960",
+                "[fe80::1]:27960",
+                "[2001:db8::1]:27960",
+                "[ff02::1]:27960",
+                "[::ffff:10.0.0.1]:27960",
+                "[::ffff:8.8.8.8]:27960",
+            ]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect(),
+            datagram_info: DatagramInfo::Eot,
+        };
+        response.retain_routable();
+        let expected: Vec<SocketAddr> = [
+            "1.2.3.4:27960",
+            "[2a00:1450::1]:27960",
+            "[::ffff:8.8.8.8]:27960",
+        ]
+        .iter()
+        .map(|a| a.parse().unwrap())
+        .collect();
+        assert_eq!(response.addresses, expected);
+    }
+
+    #[test]
+    fn all_filters_include_empty_and_full_servers() {
+        let filter = GetServersFilter::all();
+        assert!(filter.empty && filter.full && filter.gametype.is_none());
+        let bytes = written(|w| get_servers(Some("Xonotic"), filter).write_all_to(w));
+        assert_eq!(bytes, oob(b"getservers Xonotic 68 empty full"));
+        let bytes = written(|w| get_servers(Some("Xonotic"), Default::default()).write_all_to(w));
+        assert_eq!(bytes, oob(b"getservers Xonotic 68"));
+
+        let request = GetServersExt {
+            game_name: GameName::try_from("Xonotic").unwrap(),
+            protocol_version: ProtocolVersion::try_from("3").unwrap(),
+            filter: GetServersExtFilter::all(),
+        };
+        let bytes = written(|w| request.write_all_to(w));
+        assert_eq!(bytes, oob(b"getserversExt Xonotic 3 empty full"));
+    }
+
+    #[test]
+    fn filter_builders_write_like_struct_literals() {
+        let ctf = || GameType::try_from("ctf").unwrap();
+        let built = GetServersFilter::builder()
+            .empty()
+            .full()
+            .gametype(ctf())
+            .build();
+        let literal = GetServersFilter {
+            empty: true,
+            full: true,
+            gametype: Some(GameTypeFilter::Named(ctf())),
+            ..Default::default()
+        };
+        assert_eq!(
+            written(|w| get_servers(Some("Xonotic"), built).write_all_to(w)),
+            written(|w| get_servers(Some("Xonotic"), literal).write_all_to(w)),
+        );
+
+        let ext = |filter| GetServersExt {
+            game_name: GameName::try_from("Xonotic").unwrap(),
+            protocol_version: ProtocolVersion::try_from("3").unwrap(),
+            filter,
+        };
+        let built = GetServersExtFilter::builder()
+            .empty()
+            .gametype(4)
+            .ipv4()
+            .ipv6()
+            .build();
+        let literal = GetServersExtFilter {
+            empty: true,
+            gametype: Some(GameTypeFilter::Numeric(4)),
+            ipv4: true,
+            ipv6: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            written(|w| ext(built).write_all_to(w)),
+            written(|w| ext(literal).write_all_to(w)),
+        );
+        assert_eq!(
+            written(|w| ext(GetServersExtFilter::builder().build()).write_all_to(w)),
+            written(|w| ext(GetServersExtFilter::default()).write_all_to(w)),
+        );
+    }
+
+    #[test]
+    fn filters_write_the_gametype_last_or_first() {
+        let filter = |gametype_first| GetServersFilter {
+            empty: true,
+            full: true,
+            gametype: Some(GameTypeFilter::Named(GameType::try_from("ctf").unwrap())),
+            gametype_first,
+        };
+        let bytes = written(|w| get_servers(Some("Xonotic"), filter(false)).write_all_to(w));
+        assert_eq!(bytes, oob(b"getservers Xonotic 68 empty full ctf"));
+        let bytes = written(|w| get_servers(Some("Xonotic"), filter(true)).write_all_to(w));
+        assert_eq!(bytes, oob(b"getservers Xonotic 68 ctf empty full"));
+
+        let ext = |gametype_first| GetServersExt {
+            game_name: GameName::try_from("Xonotic").unwrap(),
+            protocol_version: ProtocolVersion::try_from("3").unwrap(),
+            filter: GetServersExtFilter {
+                empty: true,
+                full: true,
+                gametype: Some(GameTypeFilter::Numeric(3)),
+                ipv4: true,
+                ipv6: true,
+                gametype_first,
+            },
+        };
+        let bytes = written(|w| ext(false).write_all_to(w));
+        assert_eq!(
+            bytes,
+            oob(b"getserversExt Xonotic 3 empty full gametype=3 ipv4 ipv6")
+        );
+        let bytes = written(|w| ext(true).write_all_to(w));
+        assert_eq!(
+            bytes,
+            oob(b"getserversExt Xonotic 3 gametype=3 empty full ipv4 ipv6")
+        );
+    }
+
+    #[test]
+    fn game_type_filter_numeric_and_named() {
+        let with_gametype = |gametype: GameTypeFilter<'static>| {
+            let filter = GetServersFilter {
+                gametype: Some(gametype),
+                ..Default::default()
+            };
+            written(|w| get_servers(Some("Quake3Arena"), filter).write_all_to(w))
+        };
+        assert_eq!(
+            with_gametype(GameTypeFilter::Numeric(3)),
+            oob(b"getservers Quake3Arena 68 gametype=3")
+        );
+        assert_eq!(
+            with_gametype(GameTypeFilter::Named(GameType::try_from("ctf").unwrap())),
+            oob(b"getservers Quake3Arena 68 ctf")
+        );
+        // A name that looks like a number is still written as is.
+        assert_eq!(
+            with_gametype(GameTypeFilter::Named(GameType::try_from("3").unwrap())),
+            oob(b"getservers Quake3Arena 68 3")
+        );
+        assert!(matches!(
+            GameTypeFilter::from(0),
+            GameTypeFilter::Numeric(0)
+        ));
+
+        let bytes = oob(b"getservers Quake3Arena 68 gametype=3");
+        let request = GetServers::parse(&bytes).unwrap();
+        assert!(matches!(
+            request.filter.gametype,
+            Some(GameTypeFilter::Numeric(3))
+        ));
+        let bytes = oob(b"getservers Quake3Arena 68 ctf");
+        let request = GetServers::parse(&bytes).unwrap();
+        assert!(matches!(
+            request.filter.gametype,
+            Some(GameTypeFilter::Named(ref name)) if &**name == b"ctf"
+        ));
+    }
+
+    #[test]
+    fn game_type_with_whitespace_is_rejected() {
+        for gametype in ["capture flag", " ctf", "ctf\n", "c\tf"] {
+            assert_eq!(GameType::try_from(gametype).unwrap_err(), NewGameTypeError);
+        }
+        assert!(GameType::try_from(b"ctf".to_vec()).is_ok());
+    }
 }
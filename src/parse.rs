@@ -1,36 +1,42 @@
 #![allow(non_snake_case)]
 
-use std::{
-    collections::HashMap,
-    net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
-};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 
 use nom::{
     branch::alt,
     bytes::complete::{tag, take, take_until, take_while, take_while1},
-    character::complete::char,
-    combinator::recognize,
-    multi::{many0, many1},
-    sequence::{pair, tuple},
+    character::complete::{char, satisfy},
+    combinator::{all_consuming, eof, map_opt, not, opt, peek, recognize, rest, verify},
+    multi::{many0, many_m_n},
+    sequence::{pair, preceded, terminated, tuple},
     Parser,
 };
 use nom_supreme::{error::ErrorTree, ParserExt};
 
+use crate::{Challenge, GameName, ParseLimits, PlayerInfoRef, ProtocolString, ProtocolVersion};
+
 type IResult<'a, T> = nom::IResult<&'a [u8], T, ErrorTree<&'a [u8]>>;
 
-fn oob(i: &[u8]) -> IResult<&[u8]> {
+fn oob(i: &[u8]) -> IResult<'_, &[u8]> {
     tag(super::OOB)
         .context(r#"OOB (Out of band) string b"\xFF\xFF\xFF\xFF""#)
         .parse(i)
 }
 
-fn eot(i: &[u8]) -> IResult<&[u8]> {
-    tag(b"\\EOT\0\0\0")
+fn eot(i: &[u8]) -> IResult<'_, &[u8]> {
+    tag(super::EOT)
         .context(r#"EOT (End of transmission) string b"\\\\EOT\x00\x00\x00""#)
         .parse(i)
 }
 
-fn socket_addr_v4(i: &[u8]) -> IResult<SocketAddrV4> {
+/// A `\n`, or a `\r\n` as sent by some servers running on Windows.
+fn newline(i: &[u8]) -> IResult<'_, &[u8]> {
+    alt((tag(b"\r\n"), tag(b"\n")))
+        .context(r#"b"\n" or b"\r\n""#)
+        .parse(i)
+}
+
+fn socket_addr_v4(i: &[u8]) -> IResult<'_, SocketAddrV4> {
     let (i, (_, ip, port)) = tuple((
         tag(b"\\").context(r#"IPv4 socket starts with b"\\"#),
         take(4usize).context(r#"4 bytes of IPv4 address"#),
@@ -45,7 +51,7 @@ fn socket_addr_v4(i: &[u8]) -> IResult<SocketAddrV4> {
     Ok((i, socket))
 }
 
-fn socket_addr_v6(i: &[u8]) -> IResult<SocketAddrV6> {
+fn socket_addr_v6(i: &[u8]) -> IResult<'_, SocketAddrV6> {
     let (i, (_, ip, port)) = tuple((
         tag(b"/").context(r#"IPv6 socket starts with b"/""#),
         take(16usize).context(r#"16 bytes of IPv6 address"#),
@@ -60,10 +66,10 @@ fn socket_addr_v6(i: &[u8]) -> IResult<SocketAddrV6> {
     Ok((i, socket))
 }
 
-fn socket_addr(i: &[u8]) -> IResult<SocketAddr> {
+fn socket_addr(i: &[u8]) -> IResult<'_, SocketAddr> {
     alt((
-        socket_addr_v4.map(|s| SocketAddr::from(s)),
-        socket_addr_v6.map(|s| SocketAddr::from(s)),
+        socket_addr_v4.map(SocketAddr::from),
+        socket_addr_v6.map(SocketAddr::from),
     ))
     .context("Alternate between IPv4 and IPv6 address")
     .parse(i)
@@ -71,105 +77,309 @@ fn socket_addr(i: &[u8]) -> IResult<SocketAddr> {
 
 pub struct ContainsEot(pub bool);
 
-enum Either<L, R> {
-    Left(L),
-    Right(R),
+enum Entry<A> {
+    Address(A),
+    Eot,
+    /// An entry of another address family than the response's.
+    Skipped,
 }
 
-pub fn getserversResponse(i: &[u8]) -> IResult<(Vec<SocketAddrV4>, ContainsEot)> {
+/// Also returns the number of IPv6 entries found in the IPv4 list, which
+/// some buggy masters send, skipped rather than failing the whole list.
+pub fn getserversResponse(i: &[u8]) -> IResult<'_, (Vec<SocketAddrV4>, ContainsEot, usize)> {
     let (i, (_, _)) = tuple((
         oob,
         tag(b"getserversResponse").context(r#"b"getserversResponse""#),
     ))
     .context("getserversResponse message")
     .parse(i)?;
-    let (i, mut list) = many0(
+    let (i, list) = many0(
         alt((
-            eot.map(|_| Either::Right(())),
-            socket_addr_v4.map(Either::Left),
+            eot.map(|_| Entry::Eot),
+            socket_addr_v4.map(Entry::Address),
+            socket_addr_v6.map(|_| Entry::Skipped),
         ))
-        .context("Alternate between EOT and SocketAddrV4"),
+        .context("Alternate between EOT, SocketAddrV4 and a stray SocketAddrV6"),
     )
     .context("List of SocketAddrV4 with optional EOT")
     .parse(i)?;
-    let contains_eot = matches!(list.last(), Some(Either::Right(())));
-    if contains_eot {
-        list.pop();
-    }
+    // Datagrams merged by a relay may carry an EOT before the last address.
+    let contains_eot = list.iter().any(|a| matches!(a, Entry::Eot));
+    let skipped = list.iter().filter(|a| matches!(a, Entry::Skipped)).count();
     let list = list
         .into_iter()
         .filter_map(|a| match a {
-            Either::Left(x) => Some(x),
-            Either::Right(()) => None,
+            Entry::Address(x) => Some(x),
+            Entry::Eot | Entry::Skipped => None,
         })
         .collect();
-    Ok((i, (list, ContainsEot(contains_eot))))
+    Ok((i, (list, ContainsEot(contains_eot), skipped)))
 }
 
-pub fn getserversExtResponse(i: &[u8]) -> IResult<(Vec<SocketAddr>, ContainsEot)> {
+pub fn getserversExtResponse(i: &[u8]) -> IResult<'_, (Vec<SocketAddr>, ContainsEot)> {
     let (i, (_, _)) = tuple((
         oob,
         tag(b"getserversExtResponse").context(r#"b"getserversExtResponse""#),
     ))
     .context("getserversExtResponse message")
     .parse(i)?;
-    let (i, mut list) = many0(
-        alt((
-            eot.map(|_| Either::Right(())),
-            socket_addr.map(Either::Left),
-        ))
-        .context("Alternate between EOT and SocketAddr"),
+    let (i, list) = many0(
+        alt((eot.map(|_| Entry::Eot), socket_addr.map(Entry::Address)))
+            .context("Alternate between EOT and SocketAddr"),
     )
     .context("List of SocketAddr with optional EOT")
     .parse(i)?;
-    let contains_eot = matches!(list.last(), Some(Either::Right(())));
-    if contains_eot {
-        list.pop();
-    }
+    // Datagrams merged by a relay may carry an EOT before the last address.
+    let contains_eot = list.iter().any(|a| matches!(a, Entry::Eot));
     let list = list
         .into_iter()
         .filter_map(|a| match a {
-            Either::Left(x) => Some(x),
-            Either::Right(()) => None,
+            Entry::Address(x) => Some(x),
+            Entry::Eot | Entry::Skipped => None,
         })
         .collect();
     Ok((i, (list, ContainsEot(contains_eot))))
 }
 
-pub fn key_value_map(i: &[u8]) -> IResult<HashMap<&[u8], &[u8]>> {
-    let (i, o) = many1(tuple((
-        tag(b"\\").context(r#"b"\\" key prefix"#),
-        take_while(|b: u8| b != b'\\').context(r#"Take while bytes is not b'\\'"#),
-        tag(b"\\").context(r#"b"\\" value prefix"#),
-        take_while(|b: u8| b != b'\\' && b != b'\n')
-            .context(r#"Take while byte is not b'\\' or b'\n'"#),
-    )))
-    .context("Key value map")
+fn challenge(i: &[u8]) -> IResult<'_, Challenge<'_>> {
+    take_while1(|b: u8| !b.is_ascii_whitespace())
+        .map_res(Challenge::try_from)
+        .context("Challenge")
+        .parse(i)
+}
+
+pub fn getinfo(i: &[u8]) -> IResult<'_, Challenge<'_>> {
+    let (i, (_, _, challenge)) =
+        tuple((oob, tag(b"getinfo ").context(r#"b"getinfo ""#), challenge))
+            .context("getinfo")
+            .parse(i)?;
+    Ok((i, challenge))
+}
+
+pub fn getstatus(i: &[u8]) -> IResult<'_, Challenge<'_>> {
+    let (i, (_, _, challenge)) = tuple((
+        oob,
+        tag(b"getstatus ").context(r#"b"getstatus ""#),
+        challenge,
+    ))
+    .context("getstatus")
+    .parse(i)?;
+    Ok((i, challenge))
+}
+
+/// A space followed by an argument of a request, up to the next
+/// whitespace.
+fn argument(i: &[u8]) -> IResult<'_, &[u8]> {
+    preceded(
+        tag(b" ").context("Space before argument"),
+        take_while1(|b: u8| !b.is_ascii_whitespace()).context("Argument"),
+    )
+    .parse(i)
+}
+
+fn protocol_version(i: &[u8]) -> IResult<'_, ProtocolVersion<'_>> {
+    argument
+        .map_res(ProtocolVersion::try_from)
+        .context("Protocol version")
+        .parse(i)
+}
+
+fn game_name(i: &[u8]) -> IResult<'_, GameName<'_>> {
+    argument
+        .map_res(GameName::try_from)
+        .context("Game name")
+        .parse(i)
+}
+
+/// The game name, protocol version and filter tokens of a "getservers".
+///
+/// Quake3 clients omit the game name, so a first argument is only taken
+/// as the game name when a protocol version follows it. Filters are
+/// never only digits (a numeric gametype is written `gametype=<number>`),
+/// so this is not ambiguous.
+pub fn getservers(
+    i: &[u8],
+) -> IResult<'_, (Option<GameName<'_>>, ProtocolVersion<'_>, Filters<'_>)> {
+    let (i, (_, _, (game_name, protocol_version), filters, _)) = tuple((
+        oob,
+        tag(b"getservers").context(r#"b"getservers""#),
+        alt((
+            pair(game_name.map(Some), protocol_version),
+            protocol_version.map(|protocol_version| (None, protocol_version)),
+        ))
+        .context("Optional game name and protocol version"),
+        many0(argument).context("Filters"),
+        opt(newline).context("Optional trailing new line"),
+    ))
+    .context("getservers")
+    .parse(i)?;
+    Ok((i, (game_name, protocol_version, filters)))
+}
+
+/// The game name, protocol version and filter tokens of a "getserversExt".
+pub fn getserversExt(i: &[u8]) -> IResult<'_, (GameName<'_>, ProtocolVersion<'_>, Filters<'_>)> {
+    let (i, (_, _, game_name, protocol_version, filters, _)) = tuple((
+        oob,
+        tag(b"getserversExt").context(r#"b"getserversExt""#),
+        game_name,
+        protocol_version,
+        many0(argument).context("Filters"),
+        opt(newline).context("Optional trailing new line"),
+    ))
+    .context("getserversExt")
+    .parse(i)?;
+    Ok((i, (game_name, protocol_version, filters)))
+}
+
+/// Quake3 clients append arguments to the "getchallenge", they are
+/// ignored.
+pub fn getchallenge(i: &[u8]) -> IResult<'_, ()> {
+    let (i, _) = tuple((
+        oob,
+        tag(b"getchallenge").context(r#"b"getchallenge""#),
+        many0(argument).context("Ignored arguments"),
+        opt(newline).context("Optional trailing new line"),
+    ))
+    .context("getchallenge")
+    .parse(i)?;
+    Ok((i, ()))
+}
+
+/// The `challenge` of the infostring of a "getmotd", possibly in double
+/// quotes.
+pub fn getmotd(i: &[u8]) -> IResult<'_, Challenge<'_>> {
+    let (i, (_, _, challenge)) = tuple((
+        oob,
+        tag(b"getmotd ").context(r#"b"getmotd ""#),
+        map_opt(
+            alt((
+                dquoted_string.and_then(all_consuming(key_value_map)),
+                key_value_map,
+            )),
+            |key_values| {
+                key_values
+                    .into_iter()
+                    .find(|(key, _)| *key == b"challenge")
+                    .map(|(_, challenge)| challenge)
+            },
+        )
+        .map_res(Challenge::try_from)
+        .context("Infostring with a challenge, possibly in double quotes"),
+    ))
+    .context("getmotd")
+    .parse(i)?;
+    Ok((i, challenge))
+}
+
+pub fn heartbeat(i: &[u8]) -> IResult<'_, ProtocolString<'_>> {
+    let (i, (_, _, protocol_string, _)) = tuple((
+        oob,
+        tag(b"heartbeat ").context(r#"b"heartbeat ""#),
+        take_while(|b: u8| b != b'\n')
+            .map_res(ProtocolString::try_from)
+            .context("Protocol string"),
+        opt(tag(b"\n")).context("Optional new line after protocol string"),
+    ))
+    .context("heartbeat")
+    .parse(i)?;
+    Ok((i, protocol_string))
+}
+
+/// Key-value pairs in the order they appear on the wire.
+pub type KeyValues<'a> = Vec<(&'a [u8], &'a [u8])>;
+
+/// The filter tokens of a "getservers" or "getserversExt".
+pub type Filters<'a> = Vec<&'a [u8]>;
+
+pub fn key_value_map(i: &[u8]) -> IResult<'_, KeyValues<'_>> {
+    key_value_map_limited(ParseLimits::NONE)(i)
+}
+
+/// Key value map failing on a key or a value longer than `limits` allow.
+pub fn key_value_map_limited(
+    limits: ParseLimits,
+) -> impl FnMut(&[u8]) -> IResult<'_, KeyValues<'_>> {
+    move |i| {
+        let (i, o) = many0(tuple((
+            tag(b"\\").context(r#"b"\\" key prefix"#),
+            verify(
+                take_while(|b: u8| b != b'\\').context(r#"Take while bytes is not b'\\'"#),
+                |key: &[u8]| key.len() <= limits.max_key_len,
+            )
+            .context("Key no longer than the limit")
+            .cut(),
+            tag(b"\\").context(r#"b"\\" value prefix"#),
+            verify(
+                take_while(|b: u8| !matches!(b, b'\\' | b'\r' | b'\n'))
+                    .context(r#"Take while byte is not b'\\', b'\r' or b'\n'"#),
+                |value: &[u8]| value.len() <= limits.max_value_len,
+            )
+            .context("Value no longer than the limit")
+            .cut(),
+        )))
+        .context("Key value map")
+        .parse(i)?;
+        let key_values = o.into_iter().map(|(_, k, _, v)| (k, v)).collect();
+        Ok((i, key_values))
+    }
+}
+
+pub fn infoResponse_limited(
+    limits: ParseLimits,
+) -> impl FnMut(&[u8]) -> IResult<'_, KeyValues<'_>> {
+    move |i| {
+        let (i, (_, _, key_values, _)) = tuple((
+            oob,
+            tag(b"infoResponse\n").context(r#"b"infoResponse\n""#),
+            key_value_map_limited(limits),
+            opt(newline).context("Optional trailing new line"),
+        ))
+        .context("infoResponse")
+        .parse(i)?;
+        Ok((i, key_values))
+    }
+}
+
+pub fn challengeResponse(i: &[u8]) -> IResult<'_, &[u8]> {
+    let (i, (_, _, challenge, _)) = tuple((
+        oob,
+        tag(b"challengeResponse ").context(r#"b"challengeResponse ""#),
+        take_while1(|b: u8| !b.is_ascii_whitespace() && b != b'\0')
+            .context("Challenge until whitespace or NUL"),
+        rest.context("Data appended by the server"),
+    ))
+    .context("challengeResponse")
     .parse(i)?;
-    let map = o.into_iter().map(|(_, k, _, v)| (k, v)).collect();
-    Ok((i, map))
+    Ok((i, challenge))
 }
 
-pub fn infoResponse(i: &[u8]) -> IResult<HashMap<&[u8], &[u8]>> {
-    let (i, (_, _, map)) = tuple((
+pub fn print(i: &[u8]) -> IResult<'_, &[u8]> {
+    let (i, (_, _, text)) = tuple((
         oob,
-        tag(b"infoResponse\n").context(r#"b"infoResponse\n""#),
-        key_value_map,
+        tag(b"print\n").context(r#"b"print\n""#),
+        rest.context("Text to print"),
     ))
-    .context("infoResponse")
+    .context("print")
     .parse(i)?;
-    Ok((i, map))
+    Ok((i, text))
 }
 
-#[derive(Debug)]
-pub struct PlayerInfo {
-    pub frags: i32,
-    pub ping: i32,
-    pub name: String,
-    pub team: i32,
+pub fn motd(i: &[u8]) -> IResult<'_, KeyValues<'_>> {
+    let (i, (_, _, key_values)) = tuple((
+        oob,
+        tag(b"motd ").context(r#"b"motd ""#),
+        alt((
+            dquoted_string.and_then(all_consuming(key_value_map)),
+            key_value_map,
+        ))
+        .context("Key value map, possibly in double quotes"),
+    ))
+    .context("motd")
+    .parse(i)?;
+    Ok((i, key_values))
 }
 
-fn dquoted_string(i: &[u8]) -> IResult<&[u8]> {
+fn dquoted_string(i: &[u8]) -> IResult<'_, &[u8]> {
     let (i, (_, text, _)) = tuple((
         tag(b"\"").context("Double quote"),
         take_until(b"\"".as_slice()).context("Take until another double quote"),
@@ -180,14 +390,8 @@ fn dquoted_string(i: &[u8]) -> IResult<&[u8]> {
     Ok((i, text))
 }
 
-fn dquoted_ascii(i: &[u8]) -> IResult<&str> {
-    dquoted_string
-        .map_res(|bytes| std::str::from_utf8(bytes))
-        .parse(i)
-}
-
-fn int(i: &[u8]) -> IResult<&[u8]> {
-    fn take_ascii_digits(i: &[u8]) -> IResult<&[u8]> {
+fn int(i: &[u8]) -> IResult<'_, &[u8]> {
+    fn take_ascii_digits(i: &[u8]) -> IResult<'_, &[u8]> {
         take_while1(|b: u8| b.is_ascii_digit())
             .context("Ascii digits [0-9]")
             .parse(i)
@@ -204,55 +408,203 @@ fn int(i: &[u8]) -> IResult<&[u8]> {
     .parse(i)
 }
 
-fn player_infos(i: &[u8]) -> IResult<Vec<PlayerInfo>> {
-    fn player(i: &[u8]) -> IResult<PlayerInfo> {
-        let info = tuple((
-            int.context("Frags"),
-            tag(b" ").context("Space after frags"),
-            int.context("Ping"),
-            tag(b" ").context("Space after ping"),
-            dquoted_ascii.context("Name"),
-            tag(b" ").context("Space after name"),
-            int.context("Team"),
-            tag(b"\n").context("New line after team"),
-        ))
+/// A signed integer, such as the negative frags of spectators. Integers
+/// outside of `i32::MIN..=i32::MAX` fail to parse rather than wrap or
+/// panic.
+fn int_i32(i: &[u8]) -> IResult<'_, i32> {
+    int.map_res(|digits| String::from_utf8_lossy(digits).parse::<i32>())
+        .context("Integer within 32-bit signed range")
+        .parse(i)
+}
+
+/// One or more player infos, failing when there are more than
+/// `max_players`.
+fn player_infos(max_players: usize) -> impl FnMut(&[u8]) -> IResult<'_, Vec<PlayerInfoRef<'_>>> {
+    /// An integer followed by a space or a new line. Anything else, such
+    /// as an address, is left to the extra fields, but an integer out of
+    /// the `i32` range fails like the frags and ping do.
+    fn team(i: &[u8]) -> IResult<'_, i32> {
+        let token = terminated(int, peek(alt((tag(b" "), newline))))
+            .context("Team")
+            .parse(i);
+        match token {
+            Err(nom::Err::Failure(e)) => Err(nom::Err::Error(e)),
+            Err(e) => Err(e),
+            Ok(_) => int_i32.context("Team").cut().parse(i),
+        }
+    }
+
+    fn player(i: &[u8]) -> IResult<'_, PlayerInfoRef<'_>> {
+        // Once a line starts like an integer, it is a player line, so an
+        // error in it fails the response instead of ending the list.
+        let info = preceded(
+            peek(satisfy(|c| c.is_ascii_digit() || c == '-')).context("Player info start"),
+            tuple((
+                int_i32.context("Frags"),
+                tag(b" ").context("Space after frags"),
+                int_i32.context("Ping"),
+                tag(b" ").context("Space after ping"),
+                dquoted_string.context("Name"),
+                opt(preceded(tag(b" ").context("Space after name"), team)).context("Optional team"),
+                opt(preceded(
+                    tag(b" ").context("Space before extra fields"),
+                    take_while(|b: u8| b != b'\r' && b != b'\n'),
+                ))
+                .context("Optional extra fields"),
+                newline.context("New line after player info"),
+            ))
+            .cut(),
+        )
         .context("Player info")
         .parse(i);
-        let (i, (frags, _, ping, _, name, _, team, _)) = info?;
+        let (i, (frags, _, ping, _, name, team, rest, _)) = info?;
         Ok((
             i,
-            PlayerInfo {
-                frags: std::str::from_utf8(frags).unwrap().parse().unwrap(),
-                ping: std::str::from_utf8(ping).unwrap().parse().unwrap(),
-                name: name.to_string(),
-                team: std::str::from_utf8(team).unwrap().parse().unwrap(),
+            PlayerInfoRef {
+                frags,
+                ping,
+                name,
+                team,
+                rest: rest.unwrap_or_default(),
             },
         ))
     }
 
-    many1(player).context("List of player info").parse(i)
+    move |i| {
+        terminated(
+            many_m_n(1, max_players, player),
+            not(player).context("No more player info than the limit"),
+        )
+        .context("List of player info")
+        .parse(i)
+    }
 }
 
-#[allow(clippy::type_complexity)]
-pub fn statusResponse(i: &[u8]) -> IResult<(HashMap<&[u8], &[u8]>, Vec<PlayerInfo>)> {
-    alt((
-        tuple((
-            oob,
-            tag(b"statusResponse\n").context(r#"b"statusResponse\n""#),
-            key_value_map.cut(),
-            tag(b"\n").context("Newline seperated player info"),
-            player_infos.cut(),
-        ))
-        .map(|(_oob, _msg, kv, _nl, player_infos)| (kv, player_infos))
-        .context("statusResponse with player infos"),
-        tuple((
+pub fn statusResponse(i: &[u8]) -> IResult<'_, (KeyValues<'_>, Vec<PlayerInfoRef<'_>>)> {
+    statusResponse_limited(ParseLimits::NONE)(i)
+}
+
+pub fn statusResponse_limited(
+    limits: ParseLimits,
+) -> impl FnMut(&[u8]) -> IResult<'_, (KeyValues<'_>, Vec<PlayerInfoRef<'_>>)> {
+    move |i| {
+        let (i, (_, _, key_values, player_infos)) = tuple((
             oob,
             tag(b"statusResponse\n").context(r#"b"statusResponse\n""#),
-            key_value_map.cut(),
+            key_value_map_limited(limits).cut(),
+            opt(alt((
+                preceded(newline, eof)
+                    .map(|_| vec![])
+                    .context("Trailing new line without player infos"),
+                preceded(
+                    newline.context("Newline seperated player info"),
+                    player_infos(limits.max_players).cut(),
+                ),
+            )))
+            .map(Option::unwrap_or_default)
+            .context("Optional player infos"),
         ))
-        .map(|(_oob, _msg, kv)| (kv, vec![]))
-        .context("statusResponse without player infos"),
-    ))
-    .context("statusResponse with possibly player infos")
-    .parse(i)
+        .context("statusResponse")
+        .parse(i)?;
+        Ok((i, (key_values, player_infos)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PlayerInfo, StatusResponse};
+
+    fn players(lines: &[u8]) -> Vec<PlayerInfo> {
+        let bytes = [
+            &b"\xFF\xFF\xFF\xFFstatusResponse\n\\sv_hostname\\test\n"[..],
+            lines,
+        ]
+        .concat();
+        StatusResponse::parse(&bytes).unwrap().player_infos
+    }
+
+    #[test]
+    fn players_with_and_without_team() {
+        let players = players(b"10 50 \"darkplaces\" 1\n3 80 \"quake3\"\n");
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].frags, 10);
+        assert_eq!(players[0].ping, 50);
+        assert_eq!(players[0].name, b"darkplaces");
+        assert_eq!(players[0].team, Some(1));
+        assert_eq!(players[1].frags, 3);
+        assert_eq!(players[1].ping, 80);
+        assert_eq!(players[1].name, b"quake3");
+        assert_eq!(players[1].team, None);
+    }
+
+    #[test]
+    fn player_with_negative_frags_and_zero_ping() {
+        let players = players(b"-9999 0 \"spectator\"\n");
+        assert_eq!(players[0].frags, -9999);
+        assert_eq!(players[0].ping, 0);
+    }
+
+    #[test]
+    fn player_with_minimum_frags() {
+        let players = players(b"-2147483648 50 \"name\" 1\n");
+        assert_eq!(players[0].frags, i32::MIN);
+    }
+
+    #[test]
+    fn player_with_extra_field() {
+        let players = players(b"0 50 \"name\" 1 127.0.0.1:27960\n");
+        assert_eq!(players[0].team, Some(1));
+        assert_eq!(players[0].rest, b"127.0.0.1:27960");
+    }
+
+    #[test]
+    fn player_with_address_instead_of_team() {
+        let players = players(b"0 50 \"name\" 127.0.0.1:27960\n");
+        assert_eq!(players[0].name, b"name");
+        assert_eq!(players[0].team, None);
+        assert_eq!(players[0].rest, b"127.0.0.1:27960");
+    }
+
+    #[test]
+    fn player_with_non_integer_field_instead_of_team() {
+        for (line, rest) in [
+            (&b"0 50 \"name\" 1.5\n"[..], &b"1.5"[..]),
+            (b"0 50 \"name\" -x\n", b"-x"),
+        ] {
+            let players = players(line);
+            assert_eq!(players[0].team, None);
+            assert_eq!(players[0].rest, rest);
+        }
+    }
+
+    #[test]
+    fn players_with_crlf_line_endings() {
+        let bytes = b"\xFF\xFF\xFF\xFFstatusResponse\n\\sv_hostname\\test\r\n\
+            10 50 \"first\" 1\r\n3 80 \"second\"\r\n";
+        let response = StatusResponse::parse(bytes).unwrap();
+        assert_eq!(response.get(b"sv_hostname"), Some(b"test".as_slice()));
+        let players = response.player_infos;
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].name, b"first");
+        assert_eq!(players[0].team, Some(1));
+        assert_eq!(players[1].name, b"second");
+        assert_eq!(players[1].team, None);
+    }
+
+    #[test]
+    fn player_with_out_of_range_integers_fails() {
+        for line in [
+            &b"99999999999999999 50 \"name\" 1\n"[..],
+            b"0 99999999999999999 \"name\" 1\n",
+            b"2147483648 50 \"name\" 1\n",
+            b"0 50 \"name\" 99999999999\n",
+        ] {
+            let bytes = [
+                &b"\xFF\xFF\xFF\xFFstatusResponse\n\\sv_hostname\\test\n"[..],
+                line,
+            ]
+            .concat();
+            assert!(StatusResponse::parse(&bytes).is_err());
+        }
+    }
 }